@@ -0,0 +1,76 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+// the commit and the date of the build, for manifest and the version builtin.
+fn main() {
+    println!("cargo:rustc-env=NSH_GIT_COMMIT={}", git_commit());
+    println!("cargo:rustc-env=NSH_BUILD_DATE={}", build_date());
+
+    // rebuild when HEAD moves, or when a reproducible build pins the date.
+    for path in git_paths() {
+        println!("cargo:rerun-if-changed={path}");
+    }
+
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+// HEAD, the ref of the current branch, and packed-refs, where a commit moves HEAD.
+// only those that exist, since cargo reruns the script every build for a path that does not,
+// e.g. every path of .git in a build from a tarball.
+fn git_paths() -> Vec<String> {
+    let mut names = vec!["HEAD".to_owned(), "packed-refs".to_owned()];
+
+    // a detached HEAD has no ref.
+    if let Some(name) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        names.push(name);
+    }
+
+    names
+        .iter()
+        .filter_map(|name| git(&["rev-parse", "--git-path", name]))
+        .filter(|path| Path::new(path).is_file())
+        .collect()
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    match Command::new("git").args(args).output() {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        }
+        _ => None,
+    }
+}
+
+// the short hash of HEAD, or unknown outside a git repository.
+fn git_commit() -> String {
+    git(&["rev-parse", "--short", "HEAD"]).unwrap_or("unknown".to_owned())
+}
+
+// YYYY-MM-DD in UTC, of SOURCE_DATE_EPOCH when it is set.
+fn build_date() -> String {
+    let secs = match env::var("SOURCE_DATE_EPOCH").map(|string| string.parse::<i64>()) {
+        Ok(Ok(secs)) => secs,
+        _ => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default(),
+    };
+
+    // days since 1970-01-01 to the civil date.
+    let days = secs.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+// the aliases, which replace the first word of a command before it is parsed,
+// and the abbreviations, which replace it in the line being edited when space is pressed.
+#[derive(Debug, Clone, Default)]
+pub struct Alias {
+    aliases: BTreeMap<String, String>,
+    abbrs: BTreeMap<String, String>,
+}
+
+impl Alias {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: String, val: String) {
+        self.aliases.insert(name, val);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.aliases.remove(name)
+    }
+
+    pub fn clear(&mut self) {
+        self.aliases.clear()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(|val| val.as_str())
+    }
+
+    pub fn aliases(&self) -> &BTreeMap<String, String> {
+        &self.aliases
+    }
+
+    pub fn insert_abbr(&mut self, name: String, val: String) {
+        self.abbrs.insert(name, val);
+    }
+
+    pub fn remove_abbr(&mut self, name: &str) -> Option<String> {
+        self.abbrs.remove(name)
+    }
+
+    pub fn get_abbr(&self, name: &str) -> Option<&str> {
+        self.abbrs.get(name).map(|val| val.as_str())
+    }
+
+    pub fn abbrs(&self) -> &BTreeMap<String, String> {
+        &self.abbrs
+    }
+
+    // what the word becomes, while the first word of the result is an alias too.
+    // an alias is not expanded again inside itself, e.g. alias ls='ls -F' or alias a=b b=a
+    pub fn expand(&self, word: &str) -> Option<String> {
+        let mut text = self.aliases.get(word)?.to_owned();
+
+        let mut expanded = HashSet::from([word.to_owned()]);
+
+        loop {
+            let trimmed = text.trim_start();
+
+            let (first, rest) = match trimmed.find(char::is_whitespace) {
+                Some(i) => trimmed.split_at(i),
+                None => (trimmed, ""),
+            };
+
+            match self.aliases.get(first) {
+                Some(val) if expanded.insert(first.to_owned()) => text = format!("{val}{rest}"),
+                _ => return Some(text),
+            }
+        }
+    }
+}
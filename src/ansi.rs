@@ -8,12 +8,12 @@ pub enum Cursor {
 
 impl Cursor {
     pub fn get_esc_code(&self) -> String {
-        return match &self {
+        match &self {
             Cursor::Move(position) => format!("\x1b[{position}G"),
             Cursor::Backspace => format!("\x08{}", " "),
-            Cursor::Left => format!("\x1b[1D"),
-            Cursor::Right => format!("\x1b[1C"),
-            Cursor::ClearLine=>format!("\x1b[2K"),
-        };
+            Cursor::Left => "\x1b[1D".to_owned(),
+            Cursor::Right => "\x1b[1C".to_owned(),
+            Cursor::ClearLine=>"\x1b[2K".to_owned(),
+        }
     }
 }
\ No newline at end of file
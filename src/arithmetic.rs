@@ -0,0 +1,566 @@
+use crate::error::*;
+
+// how deep a variable may refer to another expression, e.g. =A B+1 ; =B A+1
+const MAX_DEPTH: usize = 32;
+
+// where the variables of an expression are read from and assigned to.
+pub trait Scope {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, val: String) -> Result<()>;
+}
+
+// evaluates the integer expression of $(( )), (( )) and let.
+pub fn eval(source: &str, scope: &mut dyn Scope) -> Result<i64> {
+    eval_depth(source, scope, 0)
+}
+
+fn eval_depth(source: &str, scope: &mut dyn Scope, depth: usize) -> Result<i64> {
+    if depth > MAX_DEPTH {
+        Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("expression recursion level exceeded: {source}"),
+        ))?
+    }
+
+    let tokens = tokenize(source)?;
+
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+
+    let expr = parser.parse_comma()?;
+
+    if let Some(token) = parser.peek() {
+        Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("unexpected token in expression: {token}"),
+        ))?
+    }
+
+    Evaluator { scope, depth }.eval(&expr)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Name(name) => write!(f, "{name}"),
+            Token::Op(op) => write!(f, "{op}"),
+        }
+    }
+}
+
+// longest first, so that <<= is not read as << and =.
+const OPS: [&str; 39] = [
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "^", "|", "!", "~",
+    "=", "?", ":", ",", "(", ")",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars = source.chars().collect::<Vec<char>>();
+
+    let mut tokens = Vec::new();
+
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if ch.is_ascii_digit() {
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '#') {
+                i += 1;
+            }
+
+            let literal = chars[start..i].iter().collect::<String>();
+
+            tokens.push(Token::Number(parse_number(&literal)?));
+            continue;
+        }
+
+        // $NAME and ${NAME} are the same as NAME
+        if ch == '$' || ch.is_alphabetic() || ch == '_' {
+            if ch == '$' {
+                i += 1;
+            }
+
+            let is_brace = chars.get(i) == Some(&'{');
+
+            if is_brace {
+                i += 1;
+            }
+
+            let start = i;
+
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            if start == i {
+                Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("a variable name was expected in expression: {source}"),
+                ))?
+            }
+
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+
+            if is_brace {
+                if chars.get(i) != Some(&'}') {
+                    Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        format!("}} was not found in expression: {source}"),
+                    ))?
+                }
+                i += 1;
+            }
+
+            continue;
+        }
+
+        match OPS.iter().find(|op| {
+            op.chars()
+                .enumerate()
+                .all(|(n, op_ch)| chars.get(i + n) == Some(&op_ch))
+        }) {
+            Some(op) => {
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("unknown character in expression: {ch}"),
+            ))?,
+        }
+    }
+
+    Ok(tokens)
+}
+
+// 10, 0x1f, 017, 2#1010
+fn parse_number(literal: &str) -> Result<i64> {
+    let wrong = || {
+        Error::new(
+            ErrorKind::WrongSyntax,
+            format!("value too great for base: {literal}"),
+        )
+    };
+
+    let (digits, radix) = if let Some((base, digits)) = literal.split_once('#') {
+        match base.parse::<u32>() {
+            Ok(radix) if (2..=36).contains(&radix) => (digits, radix),
+            _ => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("invalid arithmetic base: {literal}"),
+            ))?,
+        }
+    } else if let Some(digits) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        (digits, 16)
+    } else if literal.len() > 1 && literal.starts_with('0') {
+        (&literal[1..], 8)
+    } else {
+        (literal, 10)
+    };
+
+    if digits.is_empty() {
+        Err(wrong())?
+    }
+
+    let mut number: i64 = 0;
+
+    for ch in digits.chars() {
+        let digit = ch.to_digit(radix).ok_or_else(wrong)?;
+
+        number = number
+            .checked_mul(radix as i64)
+            .and_then(|number| number.checked_add(digit as i64))
+            .ok_or_else(|| overflow(literal))?;
+    }
+
+    Ok(number)
+}
+
+fn overflow(source: &str) -> Error {
+    Error::new(
+        ErrorKind::Overflow,
+        format!("arithmetic overflow: {source}"),
+    )
+}
+
+#[derive(Debug)]
+enum Expr {
+    Number(i64),
+    Variable(String),
+    Unary(&'static str, Box<Expr>),
+    // ++x, --x, x++, x--
+    Step(String, i64, bool),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    // x = y, x += y, ...
+    Assign(String, &'static str, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn next_if_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek_op() {
+            Some(op) if ops.contains(&op) => {
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_op(&mut self, op: &'static str) -> Result<()> {
+        match self.next_if_op(&[op]) {
+            Some(_) => Ok(()),
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("{op} was expected in expression"),
+            )),
+        }
+    }
+
+    fn parse_comma(&mut self) -> Result<Expr> {
+        let mut left = self.parse_assign()?;
+
+        while self.next_if_op(&[","]).is_some() {
+            left = Expr::Binary(",", Box::new(left), Box::new(self.parse_assign()?));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_assign(&mut self) -> Result<Expr> {
+        if let (Some(Token::Name(name)), Some(Token::Op(op))) = (
+            self.tokens.get(self.position),
+            self.tokens.get(self.position + 1),
+        ) {
+            if matches!(
+                *op,
+                "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "<<=" | ">>=" | "&=" | "^=" | "|="
+            ) {
+                let (name, op) = (name.to_owned(), *op);
+                self.position += 2;
+
+                return Ok(Expr::Assign(name, op, Box::new(self.parse_assign()?)));
+            }
+        }
+
+        self.parse_conditional()
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expr> {
+        let condition = self.parse_binary(0)?;
+
+        if self.next_if_op(&["?"]).is_none() {
+            return Ok(condition);
+        }
+
+        let then = self.parse_assign()?;
+
+        self.expect_op(":")?;
+
+        let otherwise = self.parse_assign()?;
+
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    // from || down to * / %, all left associative.
+    fn parse_binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: [&[&str]; 10] = [
+            &["||"],
+            &["&&"],
+            &["|"],
+            &["^"],
+            &["&"],
+            &["==", "!="],
+            &["<", "<=", ">", ">="],
+            &["<<", ">>"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+
+        if level == LEVELS.len() {
+            return self.parse_power();
+        }
+
+        let mut left = self.parse_binary(level + 1)?;
+
+        while let Some(op) = self.next_if_op(LEVELS[level]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_binary(level + 1)?));
+        }
+
+        Ok(left)
+    }
+
+    // right associative
+    fn parse_power(&mut self) -> Result<Expr> {
+        let left = self.parse_unary()?;
+
+        if self.next_if_op(&["**"]).is_some() {
+            return Ok(Expr::Binary(
+                "**",
+                Box::new(left),
+                Box::new(self.parse_power()?),
+            ));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if let Some(op) = self.next_if_op(&["++", "--"]) {
+            return match self.peek() {
+                Some(Token::Name(name)) => {
+                    let name = name.to_owned();
+                    self.position += 1;
+
+                    Ok(Expr::Step(name, if op == "++" { 1 } else { -1 }, true))
+                }
+                _ => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("a variable was expected after {op}"),
+                )),
+            };
+        }
+
+        if let Some(op) = self.next_if_op(&["!", "~", "-", "+"]) {
+            return Ok(Expr::Unary(op, Box::new(self.parse_unary()?)));
+        }
+
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr> {
+        match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                self.position += 1;
+                Ok(Expr::Number(number))
+            }
+
+            Some(Token::Name(name)) => {
+                self.position += 1;
+
+                match self.next_if_op(&["++", "--"]) {
+                    Some(op) => Ok(Expr::Step(name, if op == "++" { 1 } else { -1 }, false)),
+                    None => Ok(Expr::Variable(name)),
+                }
+            }
+
+            Some(Token::Op("(")) => {
+                self.position += 1;
+
+                let expr = self.parse_comma()?;
+
+                self.expect_op(")")?;
+
+                Ok(expr)
+            }
+
+            Some(token) => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("unexpected token in expression: {token}"),
+            )),
+
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "an operand was expected at the end of expression".to_owned(),
+            )),
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    scope: &'a mut dyn Scope,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn eval(&mut self, expr: &Expr) -> Result<i64> {
+        match expr {
+            Expr::Number(number) => Ok(*number),
+
+            Expr::Variable(name) => self.get(name),
+
+            Expr::Unary(op, expr) => {
+                let val = self.eval(expr)?;
+
+                match *op {
+                    "!" => Ok((val == 0) as i64),
+                    "~" => Ok(!val),
+                    "-" => val
+                        .checked_neg()
+                        .ok_or_else(|| overflow(&format!("-{val}"))),
+                    _ => Ok(val),
+                }
+            }
+
+            Expr::Step(name, step, is_prefix) => {
+                let val = self.get(name)?;
+
+                let new_val = val.checked_add(*step).ok_or_else(|| overflow(name))?;
+
+                self.scope.set(name, new_val.to_string())?;
+
+                Ok(if *is_prefix { new_val } else { val })
+            }
+
+            Expr::Binary(op, left, right) => match *op {
+                // the right side is only evaluated when it decides the result.
+                "&&" => Ok((self.eval(left)? != 0 && self.eval(right)? != 0) as i64),
+                "||" => Ok((self.eval(left)? != 0 || self.eval(right)? != 0) as i64),
+                "," => {
+                    self.eval(left)?;
+                    self.eval(right)
+                }
+                _ => {
+                    let left = self.eval(left)?;
+                    let right = self.eval(right)?;
+
+                    operate(op, left, right)
+                }
+            },
+
+            Expr::Assign(name, op, expr) => {
+                let right = self.eval(expr)?;
+
+                let val = match op.strip_suffix('=') {
+                    Some("") | None => right,
+                    Some(op) => operate(op, self.get(name)?, right)?,
+                };
+
+                self.scope.set(name, val.to_string())?;
+
+                Ok(val)
+            }
+
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.eval(condition)? != 0 {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+        }
+    }
+
+    // an unset or empty variable is 0, any other value is evaluated as an expression.
+    fn get(&mut self, name: &str) -> Result<i64> {
+        let val = self.scope.get(name).unwrap_or_default();
+
+        let val = val.trim();
+
+        if val.is_empty() {
+            return Ok(0);
+        }
+
+        match parse_number(val) {
+            Ok(number) => Ok(number),
+            Err(_) => eval_depth(val, self.scope, self.depth + 1),
+        }
+    }
+}
+
+fn operate(op: &str, left: i64, right: i64) -> Result<i64> {
+    let source = format!("{left} {op} {right}");
+
+    match op {
+        "+" => left.checked_add(right).ok_or_else(|| overflow(&source)),
+        "-" => left.checked_sub(right).ok_or_else(|| overflow(&source)),
+        "*" => left.checked_mul(right).ok_or_else(|| overflow(&source)),
+        "/" | "%" => {
+            if right == 0 {
+                Err(Error::new(
+                    ErrorKind::DivisionByZero,
+                    format!("division by zero: {source}"),
+                ))?
+            }
+
+            match op {
+                "/" => left.checked_div(right),
+                _ => left.checked_rem(right),
+            }
+            .ok_or_else(|| overflow(&source))
+        }
+        "**" => {
+            if right < 0 {
+                Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("exponent less than 0: {source}"),
+                ))?
+            }
+
+            u32::try_from(right)
+                .ok()
+                .and_then(|right| left.checked_pow(right))
+                .ok_or_else(|| overflow(&source))
+        }
+        "<<" | ">>" => {
+            let shift = u32::try_from(right)
+                .ok()
+                .filter(|shift| *shift < i64::BITS)
+                .ok_or_else(|| overflow(&source))?;
+
+            match op {
+                "<<" => Ok(left << shift),
+                _ => Ok(left >> shift),
+            }
+        }
+        "<" => Ok((left < right) as i64),
+        "<=" => Ok((left <= right) as i64),
+        ">" => Ok((left > right) as i64),
+        ">=" => Ok((left >= right) as i64),
+        "==" => Ok((left == right) as i64),
+        "!=" => Ok((left != right) as i64),
+        "&" => Ok(left & right),
+        "^" => Ok(left ^ right),
+        "|" => Ok(left | right),
+        _ => Err(Error::new(
+            ErrorKind::Internal,
+            format!("unknown operator: {op}"),
+        )),
+    }
+}
@@ -0,0 +1,130 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+// alias, unalias and abbr
+pub struct Alias {
+    pub name: &'static str,
+}
+
+impl Builtin for Alias {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        match self.name {
+            "alias" => {
+                "alias [NAME[=VALUE] ...]
+    defines an alias, which replaces the first word of a command, or shows the aliases."
+            }
+            "unalias" => {
+                "unalias [-a] NAME ...
+    removes the aliases, or every alias with -a."
+            }
+            _ => {
+                "abbr [NAME=VALUE ...] [-a NAME EXPANSION ...] [-e NAME ...]
+    defines an abbreviation, which is expanded in the line being edited when space is pressed."
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let program = self.name;
+
+        let mut status = 0;
+
+        let mut not_found = |name: &str| {
+            writeln!(stderr, "{program}: {name}: not found").ok();
+            status = 1;
+        };
+
+        let alias = &mut state.alias;
+
+        match (program, args.first().map(|arg| arg.as_str())) {
+            ("alias", None) => {
+                for (name, val) in alias.aliases() {
+                    writeln!(stdout, "alias {name}={}", quote(val)).ok();
+                }
+            }
+            ("alias", _) => {
+                for arg in args {
+                    match arg.split_once('=') {
+                        Some((name, val)) => alias.insert(name.to_owned(), val.to_owned()),
+                        None => match alias.get(&arg) {
+                            Some(val) => {
+                                writeln!(stdout, "alias {arg}={}", quote(val)).ok();
+                            }
+                            None => not_found(&arg),
+                        },
+                    }
+                }
+            }
+            ("unalias", Some("-a")) => alias.clear(),
+            ("unalias", _) => {
+                for name in args {
+                    if alias.remove(&name).is_none() {
+                        not_found(&name);
+                    }
+                }
+            }
+            // abbr, abbr NAME=VAL, abbr -a NAME VAL... and abbr -e NAME...
+            ("abbr", None) => {
+                for (name, val) in alias.abbrs() {
+                    writeln!(stdout, "abbr {name}={}", quote(val)).ok();
+                }
+            }
+            ("abbr", Some("-a")) => match args.get(1) {
+                Some(name) if args.len() > 2 => {
+                    alias.insert_abbr(name.to_owned(), args[2..].join(" "))
+                }
+                _ => {
+                    return super::report(
+                        stderr,
+                        Error::new(
+                            ErrorKind::ExecutionFailed,
+                            "abbr: usage: abbr -a NAME EXPANSION...".to_owned(),
+                        ),
+                    )
+                }
+            },
+            ("abbr", Some("-e")) => {
+                for name in args.iter().skip(1) {
+                    if alias.remove_abbr(name).is_none() {
+                        not_found(name);
+                    }
+                }
+            }
+            _ => {
+                for arg in args {
+                    match arg.split_once('=') {
+                        Some((name, val)) => alias.insert_abbr(name.to_owned(), val.to_owned()),
+                        None => match alias.get_abbr(&arg) {
+                            Some(val) => {
+                                writeln!(stdout, "abbr {arg}={}", quote(val)).ok();
+                            }
+                            None => not_found(&arg),
+                        },
+                    }
+                }
+            }
+        }
+
+        status
+    }
+}
+
+// 'string', as the shell would read it back.
+pub fn quote(string: &str) -> String {
+    format!("'{}'", string.replace('\'', "'\\''"))
+}
@@ -0,0 +1,576 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::f64::consts;
+use std::io;
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Write;
+
+pub struct Calc;
+
+impl Builtin for Calc {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn help(&self) -> &str {
+        "calc [-p PRECISION] [-x | -o | -b] [EXPRESSION ...]
+    evaluates a floating-point expression, or every line of stdin when there is none.
+    the precision defaults to NSH_CALC_PRECISION."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let precision = state.lookup("NSH_CALC_PRECISION");
+
+        match calc(args, precision, stdin, stdout) {
+            Ok(_) => 0,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+// calc [-p PRECISION] [-x | -o | -b] [EXPRESSION ...]
+// evaluates the expression given as arguments, or every line of stdin when there is none.
+// precision defaults to NSH_CALC_PRECISION, and to the shortest exact form without it.
+pub fn calc(
+    args: Vec<String>,
+    precision: Option<String>,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+) -> Result<()> {
+    let mut format = Format {
+        precision: match precision {
+            Some(string) => Some(parse_precision(&string)?),
+            None => None,
+        },
+        radix: 10,
+    };
+
+    let mut expression = Vec::new();
+
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" => match args.next() {
+                Some(string) => format.precision = Some(parse_precision(&string)?),
+                None => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    "calc: -p requires a precision".to_owned(),
+                ))?,
+            },
+            "-x" => format.radix = 16,
+            "-o" => format.radix = 8,
+            "-b" => format.radix = 2,
+            _ => expression.push(arg),
+        }
+    }
+
+    if !expression.is_empty() {
+        let val = eval(&expression.join(" "))?;
+
+        return write_line(stdout, &format.format(val)?);
+    }
+
+    for line in io::BufReader::new(stdin).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?,
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let val = eval(line)?;
+
+        write_line(stdout, &format.format(val)?)?;
+    }
+
+    Ok(())
+}
+
+fn write_line(stdout: &mut dyn Write, string: &str) -> Result<()> {
+    match writeln!(stdout, "{string}") {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Error::new(ErrorKind::ExecutionFailed, err.to_string())),
+    }
+}
+
+fn parse_precision(string: &str) -> Result<usize> {
+    match string.parse::<usize>() {
+        Ok(precision) => Ok(precision),
+        Err(_) => Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("calc: invalid precision: {string}"),
+        )),
+    }
+}
+
+struct Format {
+    precision: Option<usize>,
+    radix: u32,
+}
+
+impl Format {
+    fn format(&self, val: f64) -> Result<String> {
+        if self.radix == 10 {
+            return match self.precision {
+                Some(precision) => Ok(format!("{val:.precision$}")),
+                None => Ok(format!("{val}")),
+            };
+        }
+
+        if val.fract() != 0.0 || val.abs() >= i64::MAX as f64 {
+            Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!("calc: only integers can be shown in base {}: {val}", self.radix),
+            ))?
+        }
+
+        let (sign, n) = match val < 0.0 {
+            true => ("-", (-val) as u64),
+            false => ("", val as u64),
+        };
+
+        Ok(match self.radix {
+            16 => format!("{sign}{n:#x}"),
+            8 => format!("{sign}{n:#o}"),
+            _ => format!("{sign}{n:#b}"),
+        })
+    }
+}
+
+// evaluates a floating-point expression, e.g. sqrt(2) * 4 KiB
+pub fn eval(source: &str) -> Result<f64> {
+    let mut parser = Parser {
+        chars: source.chars().collect(),
+        position: 0,
+    };
+
+    let val = parser.parse_expr()?;
+
+    parser.skip_whitespace();
+
+    if let Some(ch) = parser.peek() {
+        Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("calc: unexpected character: {ch}"),
+        ))?
+    }
+
+    if val.is_nan() {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("calc: math domain error: {source}"),
+        ))?
+    }
+
+    Ok(val)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|ch| ch.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn next_if(&mut self, ch: char) -> bool {
+        self.skip_whitespace();
+
+        if self.peek() == Some(ch) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, ch: char) -> Result<()> {
+        if !self.next_if(ch) {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("calc: {ch} was expected"),
+            ))?
+        }
+
+        Ok(())
+    }
+
+    // + -
+    fn parse_expr(&mut self) -> Result<f64> {
+        let mut val = self.parse_term()?;
+
+        loop {
+            if self.next_if('+') {
+                val += self.parse_term()?;
+            } else if self.next_if('-') {
+                val -= self.parse_term()?;
+            } else {
+                return Ok(val);
+            }
+        }
+    }
+
+    // * / %
+    fn parse_term(&mut self) -> Result<f64> {
+        let mut val = self.parse_unary()?;
+
+        loop {
+            self.skip_whitespace();
+
+            // ** is the power, not a product
+            if self.peek() == Some('*') && self.chars.get(self.position + 1) != Some(&'*') {
+                self.position += 1;
+                val *= self.parse_unary()?;
+            } else if self.next_if('/') {
+                val = divide(val, self.parse_unary()?, "/")?;
+            } else if self.next_if('%') {
+                val = divide(val, self.parse_unary()?, "%")?;
+            } else {
+                return Ok(val);
+            }
+        }
+    }
+
+    // -2 ^ 2 is -4, like in math
+    fn parse_unary(&mut self) -> Result<f64> {
+        if self.next_if('-') {
+            return Ok(-self.parse_unary()?);
+        }
+
+        if self.next_if('+') {
+            return self.parse_unary();
+        }
+
+        self.parse_power()
+    }
+
+    // ^ and **, right associative
+    fn parse_power(&mut self) -> Result<f64> {
+        let base = self.parse_primary()?;
+
+        self.skip_whitespace();
+
+        if self.next_if('^') {
+            return Ok(base.powf(self.parse_unary()?));
+        }
+
+        if self.peek() == Some('*') && self.chars.get(self.position + 1) == Some(&'*') {
+            self.position += 2;
+            return Ok(base.powf(self.parse_unary()?));
+        }
+
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> Result<f64> {
+        self.skip_whitespace();
+
+        let val = match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let val = self.parse_expr()?;
+                self.expect(')')?;
+                val
+            }
+
+            Some(ch) if ch.is_ascii_digit() || ch == '.' => self.parse_number()?,
+
+            Some(ch) if ch.is_alphabetic() || ch == '_' => {
+                let name = self.parse_name();
+
+                if self.next_if('(') {
+                    let mut args = Vec::new();
+
+                    if !self.next_if(')') {
+                        loop {
+                            args.push(self.parse_expr()?);
+
+                            if self.next_if(',') {
+                                continue;
+                            }
+
+                            self.expect(')')?;
+                            break;
+                        }
+                    }
+
+                    call(&name, &args)?
+                } else {
+                    constant(&name)?
+                }
+            }
+
+            Some(ch) => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("calc: unexpected character: {ch}"),
+            ))?,
+
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "calc: an operand was expected at the end".to_owned(),
+            ))?,
+        };
+
+        Ok(val * self.parse_unit())
+    }
+
+    fn parse_name(&mut self) -> String {
+        let mut name = String::new();
+
+        while let Some(ch) = self.peek() {
+            if ch.is_alphanumeric() || ch == '_' {
+                name.push(ch);
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        name
+    }
+
+    // 10, 1.5, 2e-3, 0x1f, 0o17, 0b101
+    fn parse_number(&mut self) -> Result<f64> {
+        if self.peek() == Some('0') {
+            let radix = match self.chars.get(self.position + 1) {
+                Some('x' | 'X') => 16,
+                Some('o' | 'O') => 8,
+                Some('b' | 'B') => 2,
+                _ => 10,
+            };
+
+            if radix != 10 {
+                self.position += 2;
+
+                let mut digits = String::new();
+
+                while let Some(ch) = self.peek() {
+                    if ch.is_digit(radix) || ch == '_' {
+                        digits.push(ch);
+                        self.position += 1;
+                    } else {
+                        break;
+                    }
+                }
+
+                return match i64::from_str_radix(&digits.replace('_', ""), radix) {
+                    Ok(n) => Ok(n as f64),
+                    Err(err) => Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        format!("calc: invalid number in base {radix}: {err}"),
+                    )),
+                };
+            }
+        }
+
+        let mut literal = String::new();
+
+        while let Some(ch) = self.peek() {
+            let is_exponent = matches!(ch, 'e' | 'E')
+                && self.chars.get(self.position + 1).is_some_and(|next| {
+                    next.is_ascii_digit()
+                        || (matches!(next, '+' | '-')
+                            && self
+                                .chars
+                                .get(self.position + 2)
+                                .is_some_and(|ch| ch.is_ascii_digit()))
+                });
+
+            if ch.is_ascii_digit() || ch == '.' || ch == '_' {
+                literal.push(ch);
+                self.position += 1;
+            } else if is_exponent {
+                literal.push(ch);
+                self.position += 1;
+
+                if let Some(sign @ ('+' | '-')) = self.peek() {
+                    literal.push(sign);
+                    self.position += 1;
+                }
+            } else {
+                break;
+            }
+        }
+
+        match literal.replace('_', "").parse::<f64>() {
+            Ok(n) => Ok(n),
+            Err(_) => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("calc: invalid number: {literal}"),
+            )),
+        }
+    }
+
+    // 4 KiB, 1.5GB, sqrt(4) KiB
+    fn parse_unit(&mut self) -> f64 {
+        let start = self.position;
+
+        self.skip_whitespace();
+
+        let name = self.parse_name();
+
+        match unit(&name) {
+            Some(scale) => scale,
+            None => {
+                self.position = start;
+                1.0
+            }
+        }
+    }
+}
+
+fn unit(name: &str) -> Option<f64> {
+    let scale = match name {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024f64.powi(2),
+        "GiB" => 1024f64.powi(3),
+        "TiB" => 1024f64.powi(4),
+        "PiB" => 1024f64.powi(5),
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "PB" => 1e15,
+        _ => return None,
+    };
+
+    Some(scale)
+}
+
+fn constant(name: &str) -> Result<f64> {
+    match name {
+        "pi" => Ok(consts::PI),
+        "tau" => Ok(consts::TAU),
+        "e" => Ok(consts::E),
+        "phi" => Ok((1.0 + 5f64.sqrt()) / 2.0),
+        "inf" => Ok(f64::INFINITY),
+        _ => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("calc: unknown constant: {name}"),
+        )),
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Result<f64> {
+    let arity = |n: usize| -> Result<()> {
+        if args.len() != n {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("calc: {name} takes {n} argument(s), {} given", args.len()),
+            ))?
+        }
+        Ok(())
+    };
+
+    let val = match name {
+        "min" | "max" => {
+            if args.is_empty() {
+                Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("calc: {name} takes at least 1 argument"),
+                ))?
+            }
+
+            match name {
+                "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+                _ => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            }
+        }
+
+        // round(x), round(x, digits)
+        "round" => match args.len() {
+            1 => args[0].round(),
+            2 => {
+                let scale = 10f64.powf(args[1].trunc());
+                (args[0] * scale).round() / scale
+            }
+            _ => arity(1).map(|_| 0.0)?,
+        },
+
+        // log(x) is the natural logarithm, log(x, base) like in python
+        "log" => match args.len() {
+            1 => args[0].ln(),
+            2 => args[0].log(args[1]),
+            _ => arity(1).map(|_| 0.0)?,
+        },
+
+        "pow" | "atan2" | "hypot" => {
+            arity(2)?;
+
+            match name {
+                "pow" => args[0].powf(args[1]),
+                "atan2" => args[0].atan2(args[1]),
+                _ => args[0].hypot(args[1]),
+            }
+        }
+
+        _ => {
+            let function: fn(f64) -> f64 = match name {
+                "sqrt" => f64::sqrt,
+                "cbrt" => f64::cbrt,
+                "abs" => f64::abs,
+                "exp" => f64::exp,
+                "ln" => f64::ln,
+                "log2" => f64::log2,
+                "log10" => f64::log10,
+                "floor" => f64::floor,
+                "ceil" => f64::ceil,
+                "trunc" => f64::trunc,
+                "sin" => f64::sin,
+                "cos" => f64::cos,
+                "tan" => f64::tan,
+                "asin" => f64::asin,
+                "acos" => f64::acos,
+                "atan" => f64::atan,
+                _ => Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("calc: unknown function: {name}"),
+                ))?,
+            };
+
+            arity(1)?;
+
+            function(args[0])
+        }
+    };
+
+    Ok(val)
+}
+
+fn divide(left: f64, right: f64, op: &str) -> Result<f64> {
+    if right == 0.0 {
+        Err(Error::new(
+            ErrorKind::DivisionByZero,
+            format!("calc: division by zero: {left} {op} {right}"),
+        ))?
+    }
+
+    match op {
+        "/" => Ok(left / right),
+        _ => Ok(left % right),
+    }
+}
@@ -0,0 +1,279 @@
+use super::jump;
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use crate::variable::Value;
+use crate::variable::Variable;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub struct Cd;
+
+impl Builtin for Cd {
+    fn name(&self) -> &str {
+        "cd"
+    }
+
+    fn help(&self) -> &str {
+        "cd [-L | -P] [DIR | -]
+    changes the current directory to DIR, or to HOME without it, and sets PWD and OLDPWD.
+    cd - goes back to OLDPWD. a relative DIR is also looked for in the directories of CDPATH.
+    -L keeps the symbolic links in PWD, as by default, and -P resolves them."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let (is_physical, args) = match parse_options("cd", args) {
+            Ok(options) => options,
+            Err(err) => return super::report(stderr, err),
+        };
+
+        let mut args = args.into_iter();
+
+        let (arg, extra) = (args.next(), args.next());
+
+        if extra.is_some() {
+            return super::report(
+                stderr,
+                Error::new(
+                    ErrorKind::ExecutionFailed,
+                    "cd: too many arguments".to_owned(),
+                ),
+            );
+        }
+
+        // cd - and a directory found through CDPATH tell where they went.
+        let (dir, is_printed) = match arg.as_deref() {
+            None => match state.lookup("HOME") {
+                Some(home) => (home, false),
+                None => {
+                    return super::report(
+                        stderr,
+                        Error::new(ErrorKind::ExecutionFailed, "cd: HOME not set".to_owned()),
+                    )
+                }
+            },
+            Some("-") => match state.lookup("OLDPWD") {
+                Some(old) => (old, true),
+                None => {
+                    return super::report(
+                        stderr,
+                        Error::new(ErrorKind::ExecutionFailed, "cd: OLDPWD not set".to_owned()),
+                    )
+                }
+            },
+            Some(dir) => match search_cdpath(state, dir) {
+                Some(found) => (found, true),
+                None => (dir.to_owned(), false),
+            },
+        };
+
+        match cd(&mut state.variable, &dir, is_physical) {
+            Ok(pwd) => {
+                if is_printed {
+                    writeln!(stdout, "{pwd}").ok();
+                }
+
+                0
+            }
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+pub struct Pwd;
+
+impl Builtin for Pwd {
+    fn name(&self) -> &str {
+        "pwd"
+    }
+
+    fn help(&self) -> &str {
+        "pwd [-L | -P]
+    shows the current directory, as PWD keeps it with symbolic links, or with -P, without them."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let is_physical = match parse_options("pwd", args) {
+            Ok((is_physical, _)) => is_physical,
+            Err(err) => return super::report(stderr, err),
+        };
+
+        let pwd = match is_physical {
+            true => env::current_dir().ok(),
+            false => logical_dir(&state.variable),
+        };
+
+        match pwd {
+            Some(pwd) => {
+                writeln!(stdout, "{}", pwd.display()).ok();
+                0
+            }
+            None => super::report(
+                stderr,
+                Error::new(
+                    ErrorKind::ExecutionFailed,
+                    "pwd: the current directory is gone".to_owned(),
+                ),
+            ),
+        }
+    }
+}
+
+// -L and -P, the last of them counting, before the other arguments.
+fn parse_options(name: &str, args: Vec<String>) -> Result<(bool, Vec<String>)> {
+    let mut is_physical = false;
+
+    let mut args = args.into_iter().peekable();
+
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+        if arg == "--" {
+            break;
+        }
+
+        for ch in arg.chars().skip(1) {
+            match ch {
+                'L' => is_physical = false,
+                'P' => is_physical = true,
+                _ => Err(Error::new(
+                    ErrorKind::ExecutionFailed,
+                    format!("{name}: -{ch}: invalid option"),
+                ))?,
+            }
+        }
+    }
+
+    Ok((is_physical, args.collect()))
+}
+
+// a relative DIR that does not start with . or .. is looked for in each directory of CDPATH.
+// an empty entry is the current directory, which is not told as found.
+fn search_cdpath(state: &ShellState, dir: &str) -> Option<String> {
+    if dir.starts_with('/') || dir == "." || dir == ".." {
+        return None;
+    }
+
+    if dir.starts_with("./") || dir.starts_with("../") {
+        return None;
+    }
+
+    let cdpath = state.lookup("CDPATH")?;
+
+    cdpath
+        .split(':')
+        .filter(|base| !base.is_empty())
+        .map(|base| Path::new(base).join(dir))
+        .find(|path| path.is_dir())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+// changes to the directory, and returns the new PWD.
+// PWD and OLDPWD are kept in the variables of the shell, which commands get as their environment.
+// without is_physical, PWD is the path as it was written from the old PWD, .. taking off the last
+// directory of it rather than going to the parent of where a symbolic link points.
+// every directory it goes to is recorded for j.
+pub fn cd(variable: &mut Variable, dir: &str, is_physical: bool) -> Result<String> {
+    let target = match Path::new(dir).is_absolute() {
+        true => PathBuf::from(dir),
+        false => logical_dir(variable).unwrap_or_default().join(dir),
+    };
+
+    let target = normalize(&target);
+
+    let changed = match is_physical {
+        true => env::set_current_dir(dir),
+        // the logical path may not exist where .. went past a symbolic link.
+        false => env::set_current_dir(&target).or_else(|_| env::set_current_dir(dir)),
+    };
+
+    if let Err(err) = changed {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("cd: {dir}: {err}"),
+        ))?
+    }
+
+    let pwd = match is_physical || !is_same_dir(&target, Path::new(".")) {
+        true => match env::current_dir() {
+            Ok(pwd) => pwd,
+            Err(err) => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!("cd: {dir}: {err}"),
+            ))?,
+        },
+        false => target,
+    };
+
+    let pwd = pwd.to_string_lossy().to_string();
+
+    if let Some(old) = variable.get_value("PWD").cloned() {
+        variable.insert_value("OLDPWD".to_owned(), old).ok();
+        variable.attributes_mut("OLDPWD").is_exported = true;
+    }
+
+    variable
+        .insert_value("PWD".to_owned(), Value::String(pwd.to_owned()))
+        .ok();
+    variable.attributes_mut("PWD").is_exported = true;
+
+    jump::record(variable, &pwd);
+
+    Ok(pwd)
+}
+
+// PWD, when it is still the current directory.
+pub fn logical_dir(variable: &Variable) -> Option<PathBuf> {
+    let pwd = match variable.get_value("PWD") {
+        Some(Value::String(pwd)) if pwd.starts_with('/') => PathBuf::from(pwd),
+        _ => return env::current_dir().ok(),
+    };
+
+    match is_same_dir(&pwd, Path::new(".")) {
+        true => Some(pwd),
+        false => env::current_dir().ok(),
+    }
+}
+
+// the path with . and .. taken out, without looking at the files.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(name) => normalized.push(name),
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+
+    normalized
+}
+
+fn is_same_dir(left: &Path, right: &Path) -> bool {
+    match (fs::metadata(left), fs::metadata(right)) {
+        (Ok(left), Ok(right)) => left.dev() == right.dev() && left.ino() == right.ino(),
+        _ => false,
+    }
+}
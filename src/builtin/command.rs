@@ -0,0 +1,229 @@
+use super::alias::quote;
+use super::Builtin;
+use super::ExitStatus;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+// the words the parser takes for itself where a command starts.
+pub const KEYWORDS: [&str; 10] = [
+    "case", "esac", "for", "do", "done", "let", "include", "[[", "{", "}",
+];
+
+// the search path when PATH is not set.
+pub const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+pub struct Command;
+
+impl Builtin for Command {
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn help(&self) -> &str {
+        "command [-v | -V] NAME [ARG ...]
+    runs the command NAME with the arguments, not its alias.
+    with -v, shows what NAME runs, and with -V, what kind of command it is."
+    }
+
+    // command NAME [ARG ...] is run by the shell, which gives NAME and the arguments their place.
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let is_verbose = match args.first().map(|arg| arg.as_str()) {
+            Some("-v") => false,
+            Some("-V") => true,
+            _ => {
+                writeln!(stderr, "command: usage: command [-v | -V] NAME [ARG ...]").ok();
+                return 2;
+            }
+        };
+
+        let mut status = 0;
+
+        for name in args.iter().skip(1) {
+            match (resolve(state, name), is_verbose) {
+                (Some(resolution), false) => {
+                    writeln!(stdout, "{}", resolution.short(name)).ok();
+                }
+                (Some(resolution), true) => {
+                    writeln!(stdout, "{}", resolution.describe(name)).ok();
+                }
+                (None, is_verbose) => {
+                    if is_verbose {
+                        writeln!(stderr, "command: {name}: not found").ok();
+                    }
+
+                    status = 1;
+                }
+            }
+        }
+
+        status
+    }
+}
+
+// what a command name runs.
+pub enum Resolution {
+    Alias(String),
+    Keyword,
+    Builtin,
+    // remembered from an earlier search of PATH
+    Hashed(PathBuf),
+    File(PathBuf),
+}
+
+impl Resolution {
+    // as command -v shows it.
+    pub fn short(&self, name: &str) -> String {
+        match self {
+            Resolution::Alias(val) => format!("alias {name}={}", quote(val)),
+            Resolution::Keyword | Resolution::Builtin => name.to_owned(),
+            Resolution::Hashed(path) | Resolution::File(path) => path.to_string_lossy().to_string(),
+        }
+    }
+
+    // as command -V and type show it.
+    pub fn describe(&self, name: &str) -> String {
+        match self {
+            Resolution::Alias(val) => format!("{name} is aliased to `{val}'"),
+            Resolution::Keyword => format!("{name} is a shell keyword"),
+            Resolution::Builtin => format!("{name} is a shell builtin"),
+            Resolution::Hashed(path) => format!("{name} is hashed ({})", path.display()),
+            Resolution::File(path) => format!("{name} is {}", path.display()),
+        }
+    }
+
+    // as type -t shows it.
+    pub fn kind(&self) -> &str {
+        match self {
+            Resolution::Alias(_) => "alias",
+            Resolution::Keyword => "keyword",
+            Resolution::Builtin => "builtin",
+            Resolution::Hashed(_) | Resolution::File(_) => "file",
+        }
+    }
+}
+
+// an alias first, then a keyword, a builtin, a hashed path and a search of PATH, as the shell looks for them.
+pub fn resolve(state: &ShellState, name: &str) -> Option<Resolution> {
+    if let Some(val) = state.alias.get(name) {
+        return Some(Resolution::Alias(val.to_owned()));
+    }
+
+    if KEYWORDS.contains(&name) {
+        return Some(Resolution::Keyword);
+    }
+
+    if state.builtin(name).is_some() {
+        return Some(Resolution::Builtin);
+    }
+
+    let path = search_path(state);
+
+    if !name.contains('/') {
+        if let Some(found) = state.hashed.peek(&path, name) {
+            return Some(Resolution::Hashed(found));
+        }
+    }
+
+    find_in_path(&path, name).map(Resolution::File)
+}
+
+// every alias, keyword, builtin and file in PATH of the name, for type -a.
+pub fn resolve_all(state: &ShellState, name: &str) -> Vec<Resolution> {
+    let mut resolutions = Vec::new();
+
+    if let Some(val) = state.alias.get(name) {
+        resolutions.push(Resolution::Alias(val.to_owned()));
+    }
+
+    if KEYWORDS.contains(&name) {
+        resolutions.push(Resolution::Keyword);
+    }
+
+    if state.builtin(name).is_some() {
+        resolutions.push(Resolution::Builtin);
+    }
+
+    resolutions.extend(
+        find_all_in_path(&search_path(state), name)
+            .into_iter()
+            .map(Resolution::File),
+    );
+
+    resolutions
+}
+
+// the file the name runs, hashed or searched for in PATH, without hashing it.
+pub fn find(state: &ShellState, name: &str) -> Option<PathBuf> {
+    let path = search_path(state);
+
+    match name.contains('/') {
+        true => None,
+        false => state.hashed.peek(&path, name),
+    }
+    .or_else(|| find_in_path(&path, name))
+}
+
+// the file the name runs, hashed when it was searched for in PATH.
+pub fn lookup(state: &mut ShellState, name: &str) -> Option<PathBuf> {
+    let path = search_path(state);
+
+    if name.contains('/') {
+        return find_in_path(&path, name);
+    }
+
+    state.hashed.sync(&path);
+
+    if let Some(found) = state.hashed.get(name) {
+        return Some(found);
+    }
+
+    let found = find_in_path(&path, name)?;
+
+    state.hashed.insert(name.to_owned(), found.to_owned());
+
+    Some(found)
+}
+
+pub fn search_path(state: &ShellState) -> String {
+    state.lookup("PATH").unwrap_or(DEFAULT_PATH.to_owned())
+}
+
+// the executable file that runs for the name, which is taken as it is when it has a /.
+pub fn find_in_path(path: &str, name: &str) -> Option<PathBuf> {
+    find_all_in_path(path, name).into_iter().next()
+}
+
+pub fn find_all_in_path(path: &str, name: &str) -> Vec<PathBuf> {
+    if name.contains('/') {
+        return Some(PathBuf::from(name))
+            .filter(|path| is_executable(path))
+            .into_iter()
+            .collect();
+    }
+
+    path.split(':')
+        .map(|dir| match dir.is_empty() {
+            true => Path::new(".").join(name),
+            false => Path::new(dir).join(name),
+        })
+        .filter(|path| is_executable(path))
+        .collect()
+}
+
+pub fn is_executable(path: &Path) -> bool {
+    match path.metadata() {
+        Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,289 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use crate::variable::Attributes;
+use crate::variable::Value;
+use crate::variable::Variable;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::io::Write;
+
+// export, readonly, declare, local and unset
+pub struct Declare {
+    pub name: &'static str,
+}
+
+impl Builtin for Declare {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        match self.name {
+            "export" => {
+                "export [-n] [-p] [NAME[=VALUE] ...]
+    passes the variables on to the environment of commands, or lists them."
+            }
+            "readonly" => {
+                "readonly [-p] [NAME[=VALUE] ...]
+    makes the variables unchangeable, or lists them."
+            }
+            "local" => {
+                "local [-xria] [-A] [NAME[=VALUE] ...]
+    declares the variables in the scope of the block or sourced file."
+            }
+            "unset" => {
+                "unset NAME ...
+    removes the variables."
+            }
+            _ => {
+                "declare [-xria] [-A] [-p] [+xi] [NAME[=VALUE] ...]
+    gives the variables attributes, or lists them with -p."
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        match declare(state, self.name, args, stdout) {
+            Ok(_) => 0,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+// export, readonly, declare, local and unset. the variables are listed to stdout.
+fn declare(
+    state: &mut ShellState,
+    program: &str,
+    args: Vec<String>,
+    stdout: &mut dyn Write,
+) -> Result<()> {
+    let mut attributes = Attributes::default();
+
+    // attributes given with + are taken away, e.g. export -n NAME, declare +x NAME
+    let mut removed = Attributes::default();
+
+    let (mut is_array, mut is_map, mut is_print) = (false, false, false);
+
+    match program {
+        "export" => attributes.is_exported = true,
+        "readonly" => attributes.is_readonly = true,
+        "local" => attributes.is_local = true,
+        _ => {}
+    }
+
+    let mut names = Vec::new();
+
+    // -- ends the options, e.g. declare -- NAME="val" as declare -p prints it.
+    let mut is_option_end = false;
+
+    for arg in args {
+        if arg == "--" && names.is_empty() && !is_option_end {
+            is_option_end = true;
+            continue;
+        }
+
+        let flags = match arg.strip_prefix(['-', '+']) {
+            Some(flags) if names.is_empty() && !is_option_end && !flags.is_empty() => flags,
+            _ => {
+                names.push(arg);
+                continue;
+            }
+        };
+
+        // export -n is the same as declare +x
+        let is_removed = arg.starts_with('+') || (program == "export" && flags == "n");
+
+        let set = match is_removed {
+            true => &mut removed,
+            false => &mut attributes,
+        };
+
+        for flag in flags.chars() {
+            match (program, flag) {
+                ("export", 'n') => set.is_exported = true,
+                (_, 'p') => is_print = true,
+                ("unset", 'v') => {}
+                ("declare" | "local", 'x') => set.is_exported = true,
+                ("declare" | "local", 'r') => set.is_readonly = true,
+                ("declare" | "local", 'i') => set.is_integer = true,
+                ("declare" | "local", 'a') => is_array = true,
+                ("declare" | "local", 'A') => is_map = true,
+                _ => Err(Error::new(
+                    ErrorKind::ExecutionFailed,
+                    format!("{program}: unknown option: -{flag}"),
+                ))?,
+            }
+        }
+    }
+
+    if program == "unset" {
+        for name in names {
+            state.variable.remove(name)?;
+        }
+
+        return Ok(());
+    }
+
+    if removed.is_exported {
+        attributes.is_exported = false;
+    }
+
+    if removed.is_readonly {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("{program}: the readonly attribute cannot be taken away"),
+        ))?
+    }
+
+    // without names, the variables that have the attributes are listed.
+    if names.is_empty() || (is_print && !matches!(program, "declare" | "local")) {
+        let mut output = String::new();
+
+        for key in state.variable.keys() {
+            let has = state.variable.attributes(&key);
+
+            if (attributes.is_exported && !has.is_exported)
+                || (attributes.is_readonly && !has.is_readonly)
+                || (attributes.is_integer && !has.is_integer)
+                || (attributes.is_local && !has.is_local)
+            {
+                continue;
+            }
+
+            output.push_str(&describe(&state.variable, program, &key));
+        }
+
+        write!(stdout, "{output}").ok();
+
+        return Ok(());
+    }
+
+    // the names that are not set are reported after the others are listed.
+    if is_print {
+        let mut missing = Vec::new();
+
+        for name in names {
+            match state.variable.get_value(&name) {
+                Some(_) => {
+                    write!(stdout, "{}", describe(&state.variable, program, &name)).ok();
+                }
+                None => missing.push(name),
+            }
+        }
+
+        if !missing.is_empty() {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{program}: {}: not found", missing.join(", ")),
+            ))?
+        }
+
+        return Ok(());
+    }
+
+    for name in names {
+        let (name, val) = match name.split_once('=') {
+            Some((name, val)) => (name.to_owned(), Some(val.to_owned())),
+            None => (name, None),
+        };
+
+        // local NAME shadows NAME until the block ends.
+        if attributes.is_local {
+            state.variable.local(&name)?;
+        }
+
+        // the value is set before the variable becomes readonly.
+        {
+            let has = state.variable.attributes_mut(&name);
+
+            has.is_exported = (has.is_exported || attributes.is_exported) && !removed.is_exported;
+            has.is_integer = (has.is_integer || attributes.is_integer) && !removed.is_integer;
+        }
+
+        match val {
+            Some(val) => state.assign(name.to_owned(), Value::String(val))?,
+            None if is_array && state.variable.get_value(&name).is_none() => {
+                state.variable.insert_value(name.to_owned(), Value::Array(Vec::new()))?
+            }
+            None if is_map && state.variable.get_value(&name).is_none() => state
+                .variable
+                .insert_value(name.to_owned(), Value::Map(BTreeMap::new()))?,
+            None => {}
+        }
+
+        if attributes.is_readonly {
+            state.variable.attributes_mut(&name).is_readonly = true;
+        }
+    }
+
+    Ok(())
+}
+
+// a line that declares the variable again, e.g. declare -rx NAME="val"
+fn describe(variable: &Variable, program: &str, key: &str) -> String {
+    let attributes = variable.attributes(key);
+
+    let mut flags = String::new();
+
+    if let Some(Value::Array(_)) = variable.get_value(key) {
+        flags.push('a');
+    }
+
+    if let Some(Value::Map(_)) = variable.get_value(key) {
+        flags.push('A');
+    }
+
+    for (has, flag) in [
+        (attributes.is_integer, 'i'),
+        (attributes.is_readonly, 'r'),
+        (attributes.is_exported, 'x'),
+    ] {
+        if has {
+            flags.push(flag);
+        }
+    }
+
+    let val = match variable.get_value(key) {
+        Some(Value::String(string)) => format!("=\"{}\"", escape(string)),
+        Some(Value::Array(vals)) => format!("=({})", vals.join(" ")),
+        Some(Value::Tuple(vals)) => format!("=({},)", vals.join(", ")),
+        Some(Value::Map(map)) => format!(
+            "=({})",
+            map.iter()
+                .map(|(key, val)| format!("[{key}]={val}"))
+                .collect::<Vec<String>>()
+                .join(" ")
+        ),
+        None => String::new(),
+    };
+
+    match (program, flags.is_empty()) {
+        ("declare" | "local", true) => format!("declare -- {key}{val}\n"),
+        ("declare" | "local", false) => format!("declare -{flags} {key}{val}\n"),
+        _ => format!("{program} {key}{val}\n"),
+    }
+}
+
+// the string in "...", as the shell reads it back: \", \$, \` and \\ are the characters themselves.
+fn escape(string: &str) -> String {
+    let mut escaped = String::new();
+
+    for ch in string.chars() {
+        if matches!(ch, '"' | '$' | '`' | '\\') {
+            escaped.push('\\');
+        }
+
+        escaped.push(ch);
+    }
+
+    escaped
+}
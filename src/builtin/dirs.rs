@@ -0,0 +1,257 @@
+use super::cd;
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+// pushd, popd and dirs
+pub struct Dirs {
+    pub name: &'static str,
+}
+
+impl Builtin for Dirs {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        match self.name {
+            "pushd" => {
+                "pushd [-n] [DIR | +N | -N]
+    changes to DIR and puts the old directory on the directory stack.
+    without DIR, swaps the two directories on top, and with +N or -N, turns the stack
+    so that the Nth from the top or the bottom is on top. -n only changes the stack."
+            }
+            "popd" => {
+                "popd [-n] [+N | -N]
+    takes the directory on top off the stack and changes to the next,
+    or takes off the Nth from the top or the bottom. -n only changes the stack."
+            }
+            _ => {
+                "dirs [-clpv]
+    shows the directory stack, the current directory first, with HOME as ~.
+    -c clears it, -l shows HOME as it is, -p one directory a line, and -v them numbered."
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let result = match self.name {
+            "pushd" => pushd(args, state),
+            "popd" => popd(args, state),
+            _ => dirs(args, stdout, state),
+        };
+
+        match result {
+            // pushd and popd show the stack they leave.
+            Ok(_) if self.name != "dirs" => dirs(Vec::new(), stdout, state)
+                .map(|_| 0)
+                .unwrap_or_else(|err| super::report(stderr, err)),
+            Ok(_) => 0,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+fn pushd(args: Vec<String>, state: &mut ShellState) -> Result<()> {
+    let (is_moved, arg) = parse_options("pushd", args)?;
+
+    let mut stack = stack(state);
+
+    match arg {
+        None => {
+            if stack.len() < 2 {
+                Err(Error::new(
+                    ErrorKind::ExecutionFailed,
+                    "pushd: no other directory".to_owned(),
+                ))?
+            }
+
+            stack.swap(0, 1);
+        }
+        Some(arg) if arg.starts_with(['+', '-']) && arg.len() > 1 => {
+            let n = index("pushd", &arg, stack.len())?;
+            stack.rotate_left(n);
+        }
+        // -n puts DIR under the current directory.
+        Some(dir) if !is_moved => {
+            stack.insert(1, dir);
+            state.dirs = stack.split_off(1);
+            return Ok(());
+        }
+        Some(dir) => {
+            stack.insert(0, cd::cd(&mut state.variable, &dir, false)?);
+            state.dirs = stack.split_off(1);
+            return Ok(());
+        }
+    }
+
+    set_stack(state, stack)
+}
+
+fn popd(args: Vec<String>, state: &mut ShellState) -> Result<()> {
+    let (is_moved, arg) = parse_options("popd", args)?;
+
+    let mut stack = stack(state);
+
+    if stack.len() < 2 {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            "popd: directory stack empty".to_owned(),
+        ))?
+    }
+
+    // -n leaves the current directory, and takes off the one below it.
+    let n = match arg {
+        Some(arg) => index("popd", &arg, stack.len())?,
+        None => (!is_moved) as usize,
+    };
+
+    stack.remove(n);
+
+    match n {
+        0 => set_stack(state, stack),
+        _ => {
+            state.dirs = stack.split_off(1);
+            Ok(())
+        }
+    }
+}
+
+fn dirs(args: Vec<String>, stdout: &mut dyn Write, state: &mut ShellState) -> Result<()> {
+    let (mut is_long, mut is_lines, mut is_numbered) = (false, false, false);
+
+    for arg in args {
+        match arg.strip_prefix('-') {
+            Some(flags) if !flags.is_empty() => {
+                for ch in flags.chars() {
+                    match ch {
+                        'c' => state.dirs.clear(),
+                        'l' => is_long = true,
+                        'p' => is_lines = true,
+                        'v' => is_numbered = true,
+                        _ => Err(Error::new(
+                            ErrorKind::ExecutionFailed,
+                            format!("dirs: -{ch}: invalid option"),
+                        ))?,
+                    }
+                }
+            }
+            _ => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!("dirs: {arg}: invalid argument"),
+            ))?,
+        }
+    }
+
+    let home = state.lookup("HOME").filter(|home| !home.is_empty());
+
+    let stack = stack(state).into_iter().map(|dir| match (&home, is_long) {
+        (Some(home), false) if dir == *home => "~".to_owned(),
+        (Some(home), false) if dir.starts_with(&format!("{home}/")) => {
+            format!("~{}", &dir[home.len()..])
+        }
+        _ => dir,
+    });
+
+    match (is_numbered, is_lines) {
+        (true, _) => {
+            for (i, dir) in stack.enumerate() {
+                writeln!(stdout, "{i:2}  {dir}").ok();
+            }
+        }
+        (false, true) => {
+            for dir in stack {
+                writeln!(stdout, "{dir}").ok();
+            }
+        }
+        (false, false) => {
+            writeln!(stdout, "{}", stack.collect::<Vec<String>>().join(" ")).ok();
+        }
+    }
+
+    Ok(())
+}
+
+// -n, and the one argument after it.
+fn parse_options(name: &str, args: Vec<String>) -> Result<(bool, Option<String>)> {
+    let mut is_moved = true;
+
+    let mut args = args.into_iter().peekable();
+
+    if args.next_if(|arg| arg == "-n").is_some() {
+        is_moved = false;
+    }
+
+    args.next_if(|arg| arg == "--");
+
+    let arg = args.next();
+
+    if args.next().is_some() {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("{name}: too many arguments"),
+        ))?
+    }
+
+    Ok((is_moved, arg))
+}
+
+// +N counts from the top of the stack, which is 0, and -N from the bottom.
+fn index(name: &str, arg: &str, len: usize) -> Result<usize> {
+    let (digits, is_top) = match (arg.strip_prefix('+'), arg.strip_prefix('-')) {
+        (Some(digits), _) => (digits, true),
+        (_, Some(digits)) => (digits, false),
+        _ => Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("{name}: {arg}: invalid argument"),
+        ))?,
+    };
+
+    let n = match digits.parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("{name}: {arg}: invalid argument"),
+        ))?,
+    };
+
+    match (n < len, is_top) {
+        (true, true) => Ok(n),
+        (true, false) => Ok(len - 1 - n),
+        (false, _) => Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("{name}: {arg}: directory stack index out of range"),
+        )),
+    }
+}
+
+// the current directory, and the directory stack below it.
+fn stack(state: &ShellState) -> Vec<String> {
+    let pwd = cd::logical_dir(&state.variable)
+        .map(|pwd| pwd.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    [pwd]
+        .into_iter()
+        .chain(state.dirs.iter().cloned())
+        .collect()
+}
+
+// changes to the new top of the stack, and keeps the rest.
+fn set_stack(state: &mut ShellState, mut stack: Vec<String>) -> Result<()> {
+    stack[0] = cd::cd(&mut state.variable, &stack[0], false)?;
+
+    state.dirs = stack.split_off(1);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use super::printf::unescape;
+use super::printf::Escape;
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+pub struct Echo;
+
+impl Builtin for Echo {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn help(&self) -> &str {
+        "echo [-neE] [ARG ...]
+    prints the arguments separated by spaces, and a newline unless -n.
+    with -e, backslash escapes are interpreted, and \\c stops the output."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        _: &mut ShellState,
+    ) -> ExitStatus {
+        let (mut is_newline, mut is_escape) = (true, false);
+
+        // options come first, and a word of other letters, e.g. -x, is printed.
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next_if(|arg| {
+            arg.len() > 1
+                && arg.starts_with('-')
+                && arg[1..].chars().all(|ch| matches!(ch, 'n' | 'e' | 'E'))
+        }) {
+            for ch in arg[1..].chars() {
+                match ch {
+                    'n' => is_newline = false,
+                    'e' => is_escape = true,
+                    _ => is_escape = false,
+                }
+            }
+        }
+
+        let mut output = Vec::new();
+
+        for (i, arg) in args.enumerate() {
+            if i > 0 {
+                output.push(b' ');
+            }
+
+            match is_escape {
+                true => {
+                    if unescape(&arg, Escape::Echo, &mut output) {
+                        is_newline = false;
+                        break;
+                    }
+                }
+                false => output.extend_from_slice(arg.as_bytes()),
+            }
+        }
+
+        if is_newline {
+            output.push(b'\n');
+        }
+
+        match stdout.write_all(&output) {
+            Ok(_) => 0,
+            Err(err) => super::report(
+                stderr,
+                Error::new(ErrorKind::ExecutionFailed, err.to_string()),
+            ),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::shell;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+pub struct Eval;
+
+impl Builtin for Eval {
+    fn name(&self) -> &str {
+        "eval"
+    }
+
+    fn help(&self) -> &str {
+        "eval [ARG ...]
+    runs the arguments, joined by spaces, as a command line of this shell."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        _: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        state.status = 0;
+
+        match shell::eval(state, args.join(" ")) {
+            Ok(_) => state.status,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::process;
+
+pub struct Exec;
+
+impl Builtin for Exec {
+    fn name(&self) -> &str {
+        "exec"
+    }
+
+    fn help(&self) -> &str {
+        "exec [COMMAND [ARG ...]]
+    replaces the shell with the command. without a command,
+    its redirections stay on the shell, e.g. exec 2> errors.log"
+    }
+
+    // the redirections without a command are left to the shell, which applies them for good.
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let mut args = args.into_iter();
+
+        let program = match args.next() {
+            Some(program) => program,
+            None => return 0,
+        };
+
+        stdout.flush().ok();
+        io::stdout().flush().ok();
+
+        // only returns when execvp fails.
+        let err = process::Command::new(&program)
+            .args(args)
+            .env_clear()
+            .envs(state.variable.environ())
+            .exec();
+
+        match err.kind() {
+            io::ErrorKind::NotFound => {
+                super::report(
+                    stderr,
+                    Error::new(ErrorKind::NotFound, format!("exec: {program}: not found")),
+                );
+                127
+            }
+            _ => {
+                super::report(
+                    stderr,
+                    Error::new(
+                        ErrorKind::ExecutionFailed,
+                        format!("exec: {program}: {err}"),
+                    ),
+                );
+                126
+            }
+        }
+    }
+}
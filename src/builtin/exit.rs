@@ -0,0 +1,73 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+use std::process;
+
+pub struct Exit;
+
+impl Builtin for Exit {
+    fn name(&self) -> &str {
+        "exit"
+    }
+
+    fn help(&self) -> &str {
+        "exit [CODE]
+    exits the shell with the code, or with the status of the last command."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let code = match args.last() {
+            Some(arg) => match arg.parse::<i32>() {
+                Ok(code) => code,
+                Err(_) => {
+                    return super::report(
+                        stderr,
+                        Error::new(
+                            ErrorKind::ExecutionFailed,
+                            "only i32 is allowed for the exit argument".to_string(),
+                        ),
+                    )
+                }
+            },
+            None => state.status,
+        };
+
+        stdout.flush().ok();
+
+        process::exit(code)
+    }
+}
+
+pub struct Abort;
+
+impl Builtin for Abort {
+    fn name(&self) -> &str {
+        "abort"
+    }
+
+    fn help(&self) -> &str {
+        "abort
+    aborts the shell."
+    }
+
+    fn run(
+        &self,
+        _: Vec<String>,
+        _: &mut dyn Read,
+        _: &mut dyn Write,
+        _: &mut dyn Write,
+        _: &mut ShellState,
+    ) -> ExitStatus {
+        process::abort()
+    }
+}
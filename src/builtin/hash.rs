@@ -0,0 +1,245 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use base64::Engine;
+use sha2::digest::DynDigest;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+
+// every hash builtin, sha2 and sha3 being sha2-256 and sha3-256.
+pub const NAMES: [&str; 12] = [
+    "md5", "sha1", "sha2", "sha2-224", "sha2-256", "sha2-384", "sha2-512", "sha3", "sha3-224",
+    "sha3-256", "sha3-384", "sha3-512",
+];
+
+// the size of the chunks that files and stdin are read in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// md5, sha1, sha2-* and sha3-*
+pub struct Hash {
+    pub name: &'static str,
+}
+
+impl Builtin for Hash {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        "HASH [-b | --base64] [-c | --check] [FILE ...]
+    prints the checksum of each file, or of stdin without a file or with -.
+    with --check, the files are lists of checksums to verify, e.g. the output of the command."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        _: &mut ShellState,
+    ) -> ExitStatus {
+        let (mut is_base64, mut is_check) = (false, false);
+
+        let mut files = Vec::new();
+
+        for arg in args {
+            match arg.as_str() {
+                "-b" | "--base64" => is_base64 = true,
+                "-c" | "--check" => is_check = true,
+                _ => files.push(arg),
+            }
+        }
+
+        if files.is_empty() {
+            files.push("-".to_owned());
+        }
+
+        let result = match is_check {
+            true => check(self.name, &files, stdin, stdout, stderr),
+            false => print(self.name, &files, is_base64, stdin, stdout, stderr),
+        };
+
+        match result {
+            Ok(status) => status,
+            Err(err) => {
+                writeln!(stderr, "{}: {err}", self.name).ok();
+                1
+            }
+        }
+    }
+}
+
+fn hasher(name: &str) -> Result<Box<dyn DynDigest>> {
+    Ok(match name {
+        "md5" => Box::new(md5::Md5::default()),
+        "sha1" => Box::new(sha1::Sha1::default()),
+        "sha2-224" => Box::new(sha2::Sha224::default()),
+        "sha2" | "sha2-256" => Box::new(sha2::Sha256::default()),
+        "sha2-384" => Box::new(sha2::Sha384::default()),
+        "sha2-512" => Box::new(sha2::Sha512::default()),
+        "sha3-224" => Box::new(sha3::Sha3_224::default()),
+        "sha3" | "sha3-256" => Box::new(sha3::Sha3_256::default()),
+        "sha3-384" => Box::new(sha3::Sha3_384::default()),
+        "sha3-512" => Box::new(sha3::Sha3_512::default()),
+        _ => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("no such hash: {name}"),
+        ))?,
+    })
+}
+
+// the hash of everything the reader gives, read a chunk at a time.
+pub fn digest(name: &str, reader: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut hasher = hasher(name)?;
+
+    let mut chunk = vec![0; CHUNK_SIZE];
+
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(len) => hasher.update(&chunk[..len]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?,
+        }
+    }
+
+    Ok(hasher.finalize().to_vec())
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+// the hash of the file, or of stdin for -.
+fn digest_file(name: &str, path: &str, stdin: &mut dyn Read) -> Result<Vec<u8>> {
+    if path == "-" {
+        return digest(name, stdin);
+    }
+
+    match File::open(path) {
+        Ok(mut file) => digest(name, &mut file),
+        Err(err) => Err(Error::new(ErrorKind::OpenFailed, format!("{path}: {err}"))),
+    }
+}
+
+// CHECKSUM  FILE, like md5sum and sha256sum.
+// a file that cannot be read is reported, and the others are still hashed.
+fn print(
+    name: &str,
+    files: &[String],
+    is_base64: bool,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> Result<ExitStatus> {
+    let mut status = 0;
+
+    for path in files {
+        let bytes = match digest_file(name, path, stdin) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                writeln!(stderr, "{name}: {err}").ok();
+                status = 1;
+                continue;
+            }
+        };
+
+        let checksum = match is_base64 {
+            true => base64(&bytes),
+            false => hex(&bytes),
+        };
+
+        if let Err(err) = writeln!(stdout, "{checksum}  {path}") {
+            Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?
+        }
+    }
+
+    Ok(status)
+}
+
+// reads lines of CHECKSUM  FILE and prints FILE: OK or FILE: FAILED for each.
+// the checksum can be hex or base64, and FILE can be marked as binary with *.
+fn check(
+    name: &str,
+    files: &[String],
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> Result<ExitStatus> {
+    let mut lines = Vec::new();
+
+    for path in files {
+        let reader: Box<dyn BufRead + '_> = match path.as_str() {
+            "-" => Box::new(BufReader::new(&mut *stdin)),
+            _ => match File::open(path) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(err) => Err(Error::new(ErrorKind::OpenFailed, format!("{path}: {err}")))?,
+            },
+        };
+
+        for line in reader.lines() {
+            match line {
+                Ok(line) => lines.push(line),
+                Err(err) => Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?,
+            }
+        }
+    }
+
+    let (mut failed, mut unreadable, mut malformed) = (0, 0, 0);
+
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (checksum, path) = match line.split_once(' ') {
+            Some((checksum, path)) => (checksum, path.trim_start_matches([' ', '*'])),
+            None => {
+                malformed += 1;
+                continue;
+            }
+        };
+
+        let bytes = match digest_file(name, path, &mut std::io::empty()) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                writeln!(stderr, "{name}: {err}").ok();
+                writeln!(stdout, "{path}: FAILED open or read").ok();
+                unreadable += 1;
+                continue;
+            }
+        };
+
+        match checksum.eq_ignore_ascii_case(&hex(&bytes)) || checksum == base64(&bytes) {
+            true => writeln!(stdout, "{path}: OK").ok(),
+            false => {
+                failed += 1;
+                writeln!(stdout, "{path}: FAILED").ok()
+            }
+        };
+    }
+
+    for (count, message) in [
+        (malformed, "line is improperly formatted"),
+        (unreadable, "listed file could not be read"),
+        (failed, "computed checksum did NOT match"),
+    ] {
+        if count > 0 {
+            writeln!(stderr, "{name}: WARNING: {count} {message}").ok();
+        }
+    }
+
+    match failed + unreadable + malformed {
+        0 => Ok(0),
+        _ => Ok(1),
+    }
+}
@@ -0,0 +1,155 @@
+use super::command;
+use super::Builtin;
+use super::ExitStatus;
+use crate::shell::ShellState;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+
+// hash of sh, which remembers where the commands are. the digests, md5 and the like, are in hash.rs.
+pub struct HashTable;
+
+impl Builtin for HashTable {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn help(&self) -> &str {
+        "hash [-r] [-d NAME ...] [NAME ...]
+    remembers where the commands are in PATH, or shows those it remembers and how often each was run.
+    -r forgets them all, and -d the names. they are also forgotten when PATH changes."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let path = command::search_path(state);
+        state.hashed.sync(&path);
+
+        let mut args = args.into_iter().peekable();
+
+        let is_reset = args.next_if(|arg| arg == "-r").is_some();
+
+        if is_reset {
+            state.hashed.clear();
+        }
+
+        let is_delete = args.next_if(|arg| arg == "-d").is_some();
+
+        let mut status = 0;
+
+        // only hash alone shows the table.
+        let mut is_listed = !is_reset && !is_delete;
+
+        for name in args {
+            is_listed = false;
+
+            let is_found = match is_delete {
+                true => state.hashed.remove(&name),
+                false => match command::find_in_path(&path, &name) {
+                    Some(found) if !name.contains('/') => {
+                        state.hashed.insert(name.to_owned(), found);
+                        true
+                    }
+                    // a builtin is never hashed, but it is found.
+                    _ => state.builtin(&name).is_some(),
+                },
+            };
+
+            if !is_found {
+                writeln!(stderr, "hash: {name}: not found").ok();
+                status = 1;
+            }
+        }
+
+        if is_listed {
+            match state.hashed.is_empty() {
+                true => {
+                    writeln!(stdout, "hash: hash table empty").ok();
+                }
+                false => {
+                    writeln!(stdout, "hits\tcommand").ok();
+
+                    for (_, (path, hits)) in state.hashed.iter() {
+                        writeln!(stdout, "{hits:4}\t{}", path.display()).ok();
+                    }
+                }
+            }
+        }
+
+        status
+    }
+}
+
+// the commands found in PATH, and how many times each was run.
+// it is emptied when PATH is no longer the one they were found in.
+#[derive(Clone, Default)]
+pub struct PathCache {
+    path: String,
+    entries: BTreeMap<String, (PathBuf, usize)>,
+}
+
+impl PathCache {
+    pub fn sync(&mut self, path: &str) {
+        if self.path != path {
+            self.entries.clear();
+            self.path = path.to_owned();
+        }
+    }
+
+    // a path that is gone, e.g. a removed file, is forgotten.
+    pub fn get(&mut self, name: &str) -> Option<PathBuf> {
+        let (path, _) = self.entries.get(name)?;
+
+        if command::is_executable(path) {
+            return Some(path.to_owned());
+        }
+
+        self.entries.remove(name);
+
+        None
+    }
+
+    // the path as get finds it, but leaving the cache as it is, e.g. for which and type.
+    pub fn peek(&self, path: &str, name: &str) -> Option<PathBuf> {
+        if self.path != path {
+            return None;
+        }
+
+        let (path, _) = self.entries.get(name)?;
+
+        Some(path.to_owned()).filter(|path| command::is_executable(path))
+    }
+
+    pub fn insert(&mut self, name: String, path: PathBuf) {
+        self.entries.insert(name, (path, 0));
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn hit(&mut self, name: &str) {
+        if let Some((_, hits)) = self.entries.get_mut(name) {
+            *hits += 1;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &(PathBuf, usize))> {
+        self.entries.iter()
+    }
+}
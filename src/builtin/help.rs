@@ -0,0 +1,57 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+pub struct Help;
+
+impl Builtin for Help {
+    fn name(&self) -> &str {
+        "help"
+    }
+
+    fn help(&self) -> &str {
+        "help [NAME ...]
+    shows the usage of the builtins, or lists them without a name."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        // the first line of each help is its usage.
+        if args.is_empty() {
+            for name in state.builtins.names() {
+                if let Some(builtin) = state.builtins.get(&name) {
+                    writeln!(stdout, "{}", builtin.help().lines().next().unwrap_or(&name)).ok();
+                }
+            }
+
+            return 0;
+        }
+
+        let mut status = 0;
+
+        for name in args {
+            match state.builtins.get(&name) {
+                Some(builtin) => {
+                    writeln!(stdout, "{}", builtin.help()).ok();
+                }
+                None => {
+                    status = super::report(
+                        stderr,
+                        Error::new(ErrorKind::NotFound, format!("help: no builtin: {name}")),
+                    )
+                }
+            }
+        }
+
+        status
+    }
+}
@@ -0,0 +1,301 @@
+use super::cd;
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use crate::variable::Variable;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::time::SystemTime;
+
+// the file of the database, in HOME.
+pub const FILE_NAME: &str = ".nsh_jump";
+
+// the ranks are aged when their sum goes over this, so that old directories fall away.
+const MAX_RANK: f64 = 9000.0;
+
+pub struct Jump;
+
+impl Builtin for Jump {
+    fn name(&self) -> &str {
+        "j"
+    }
+
+    fn help(&self) -> &str {
+        "j [-l | -i] FRAGMENT ...
+    changes to the directory that cd went to most often and most lately of those that match,
+    that is, have the fragments in the order they are given, the last of them in the last name.
+    -l shows the directories that match by their score, and -i asks which of them to go to."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: &mut dyn io::Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        match jump(args, stdin, stdout, stderr, state) {
+            Ok(status) => status,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+fn jump(
+    args: Vec<String>,
+    stdin: &mut dyn io::Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+    state: &mut ShellState,
+) -> Result<ExitStatus> {
+    let mut args = args.into_iter().peekable();
+
+    let option = args.next_if(|arg| matches!(arg.as_str(), "-l" | "-i"));
+
+    let fragments = args.collect::<Vec<String>>();
+
+    // a directory that is there is gone to as by cd.
+    if let [dir] = fragments.as_slice() {
+        if option.is_none() && (dir == "-" || dir.contains('/') && Path::new(dir).is_dir()) {
+            return Ok(cd::Cd.run(fragments, stdin, stdout, stderr, state));
+        }
+    }
+
+    let database = match path(&state.variable) {
+        Some(path) => Database::open(path),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            "j: HOME not set".to_owned(),
+        ))?,
+    };
+
+    let pwd = cd::logical_dir(&state.variable).unwrap_or_default();
+
+    let matches = database
+        .matches(&fragments)
+        .into_iter()
+        .filter(|(_, dir)| Path::new(dir) != pwd)
+        .collect::<Vec<(f64, String)>>();
+
+    if matches.is_empty() {
+        writeln!(stderr, "j: {}: no match", fragments.join(" ")).ok();
+        return Ok(1);
+    }
+
+    let dir = match option.as_deref() {
+        Some("-l") => {
+            for (score, dir) in matches {
+                writeln!(stdout, "{score:8.1}  {dir}").ok();
+            }
+
+            return Ok(0);
+        }
+        Some(_) => {
+            for (i, (score, dir)) in matches.iter().enumerate() {
+                writeln!(stderr, "{:3}  {score:8.1}  {dir}", i + 1).ok();
+            }
+
+            write!(stderr, "j: ").ok();
+            stderr.flush().ok();
+
+            let line = read_line(stdin);
+
+            match line.trim().parse::<usize>() {
+                Ok(n) if n >= 1 && n <= matches.len() => matches[n - 1].1.to_owned(),
+                _ => return Ok(1),
+            }
+        }
+        None => matches[0].1.to_owned(),
+    };
+
+    cd::cd(&mut state.variable, &dir, false)?;
+
+    Ok(0)
+}
+
+// a line of the answer to j -i, a byte at a time so that nothing after it is taken.
+fn read_line(stdin: &mut dyn io::Read) -> String {
+    let mut line = Vec::new();
+    let mut byte = [0; 1];
+
+    while let Ok(1) = stdin.read(&mut byte) {
+        if byte[0] == b'\n' {
+            break;
+        }
+
+        line.push(byte[0]);
+    }
+
+    String::from_utf8_lossy(&line).to_string()
+}
+
+// adds the directory that cd went to, as long as there is HOME to keep the database in.
+// HOME itself is not kept, since cd goes there without an argument.
+pub fn record(variable: &Variable, dir: &str) {
+    if variable.get("HOME".to_owned()) == Some(dir) {
+        return;
+    }
+
+    if let Some(path) = path(variable) {
+        let mut database = Database::open(path);
+        database.add(dir);
+        database.save().ok();
+    }
+}
+
+// the file of the database, in HOME.
+pub fn path(variable: &Variable) -> Option<PathBuf> {
+    variable
+        .get("HOME".to_owned())
+        .map(|home| Path::new(home).join(FILE_NAME))
+}
+
+// the best directory in the database at the path that matches the fragment, for the completion of cd.
+pub fn best(path: &Path, fragment: &str) -> Option<String> {
+    Database::open(path.to_path_buf())
+        .matches(&[fragment.to_owned()])
+        .into_iter()
+        .map(|(_, dir)| dir)
+        .next()
+}
+
+// whether the directory has the fragments in their order, case aside, and the last in its last name.
+pub fn is_match(dir: &str, fragments: &[String]) -> bool {
+    let dir = dir.to_lowercase();
+
+    let mut rest = dir.as_str();
+
+    for fragment in fragments {
+        let fragment = fragment.to_lowercase();
+
+        match rest.find(&fragment) {
+            Some(i) => rest = &rest[i + fragment.len()..],
+            None => return false,
+        }
+    }
+
+    match fragments.last() {
+        Some(last) => {
+            let name = dir.rsplit('/').next().unwrap_or_default();
+            name.contains(&last.to_lowercase())
+        }
+        None => true,
+    }
+}
+
+struct Entry {
+    dir: String,
+    rank: f64,
+    // when it was last gone to, in seconds since the epoch.
+    time: u64,
+}
+
+// the directories cd went to, with how often and when, a line of RANK TIME DIR each, separated by tabs.
+struct Database {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl Database {
+    // an empty database when the file is not there yet.
+    fn open(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+
+                Some(Entry {
+                    rank: fields.next()?.parse().ok()?,
+                    time: fields.next()?.parse().ok()?,
+                    dir: fields.next()?.to_owned(),
+                })
+            })
+            .collect();
+
+        Self { path, entries }
+    }
+
+    fn add(&mut self, dir: &str) {
+        let now = now();
+
+        match self.entries.iter_mut().find(|entry| entry.dir == dir) {
+            Some(entry) => {
+                entry.rank += 1.0;
+                entry.time = now;
+            }
+            None => self.entries.push(Entry {
+                dir: dir.to_owned(),
+                rank: 1.0,
+                time: now,
+            }),
+        }
+
+        if self.entries.iter().map(|entry| entry.rank).sum::<f64>() > MAX_RANK {
+            for entry in self.entries.iter_mut() {
+                entry.rank *= 0.99;
+            }
+
+            self.entries.retain(|entry| entry.rank >= 1.0);
+        }
+    }
+
+    // written to a file of this shell beside it first, so that another shell never reads half of it
+    // nor writes over it.
+    fn save(&self) -> io::Result<()> {
+        let mut text = String::new();
+
+        for entry in self.entries.iter() {
+            text.push_str(&format!("{}\t{}\t{}\n", entry.rank, entry.time, entry.dir));
+        }
+
+        let temp = self.path.with_extension(format!("{}.tmp", process::id()));
+
+        fs::write(&temp, text)?;
+        fs::rename(&temp, &self.path)
+    }
+
+    // the directories that are still there and match, by their score, the best first.
+    fn matches(&self, fragments: &[String]) -> Vec<(f64, String)> {
+        let now = now();
+
+        let mut matches = self
+            .entries
+            .iter()
+            .filter(|entry| is_match(&entry.dir, fragments))
+            .filter(|entry| Path::new(&entry.dir).is_dir())
+            .map(|entry| (frecency(entry, now), entry.dir.to_owned()))
+            .collect::<Vec<(f64, String)>>();
+
+        matches.sort_by(|(left, _), (right, _)| right.total_cmp(left));
+
+        matches
+    }
+}
+
+// the rank, weighed by how lately the directory was gone to.
+fn frecency(entry: &Entry, now: u64) -> f64 {
+    let age = now.saturating_sub(entry.time);
+
+    let weight = match age {
+        0..=3599 => 4.0,
+        3600..=86399 => 2.0,
+        86400..=604799 => 0.5,
+        _ => 0.25,
+    };
+
+    entry.rank * weight
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -1,36 +1,153 @@
-use std::env;
-use std::io;
-use std::path::Path;
-use std::process;
+pub mod alias;
+pub mod calc;
+pub mod cd;
+pub mod command;
+pub mod declare;
+pub mod dirs;
+pub mod echo;
+pub mod eval;
+pub mod exec;
+pub mod exit;
+pub mod hash;
+pub mod hashtable;
+pub mod help;
+pub mod jump;
+pub mod printf;
+pub mod read;
+pub mod source;
+pub mod test;
+pub mod version;
+pub mod which;
 
-// pub type Command = ();
+use crate::error::Error;
+use crate::shell::ShellState;
+use std::collections::HashMap;
+use std::io::Read;
+use std::io::Write;
+use std::rc::Rc;
 
-pub fn exit(code: i32) {
-    process::exit(code)
+// the exit status of a builtin, $?
+pub type ExitStatus = i32;
+
+// a command that runs inside the shell.
+// stdin and stdout are those of where it runs, e.g. a pipe or a redirected file,
+// and it changes the shell through the state.
+pub trait Builtin {
+    fn name(&self) -> &str;
+
+    // the usage on the first line, followed by what it does, for help NAME.
+    fn help(&self) -> &str;
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus;
 }
 
-pub fn abort() {
-    process::abort()
+// the builtins by name.
+#[derive(Clone)]
+pub struct Registry(HashMap<String, Rc<dyn Builtin>>);
+
+impl Registry {
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    // a builtin of the same name is replaced.
+    pub fn register(&mut self, builtin: impl Builtin + 'static) {
+        self.0.insert(builtin.name().to_owned(), Rc::new(builtin));
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Rc<dyn Builtin>> {
+        self.0.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<dyn Builtin>> {
+        self.0.get(name).cloned()
+    }
+
+    // the names of every builtin, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.0.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+        names
+    }
 }
 
-pub fn cd(string: String) -> io::Result<()> {
-    let path = Path::new(&string);
+// every builtin of nsh.
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(exit::Exit);
+        registry.register(exit::Abort);
+        registry.register(cd::Cd);
+        registry.register(cd::Pwd);
+        registry.register(jump::Jump);
+        registry.register(calc::Calc);
+        registry.register(help::Help);
+        registry.register(echo::Echo);
+        registry.register(printf::Printf);
+        registry.register(read::Read);
+        registry.register(eval::Eval);
+        registry.register(exec::Exec);
+        registry.register(command::Command);
+        registry.register(hashtable::HashTable);
+        registry.register(version::Version);
+
+        for name in ["alias", "unalias", "abbr"] {
+            registry.register(alias::Alias { name });
+        }
+
+        for name in ["export", "readonly", "declare", "local", "unset"] {
+            registry.register(declare::Declare { name });
+        }
+
+        for name in ["source", "."] {
+            registry.register(source::Source { name });
+        }
+
+        for name in ["pushd", "popd", "dirs"] {
+            registry.register(dirs::Dirs { name });
+        }
 
-    env::set_current_dir(&path)?;
+        for name in ["test", "["] {
+            registry.register(test::Test { name });
+        }
 
-    env::set_var("PWD", path);
+        for name in ["type", "which"] {
+            registry.register(which::Which { name });
+        }
 
-    Ok(())
+        for name in hash::NAMES {
+            registry.register(hash::Hash { name });
+        }
+
+        registry
+    }
 }
 
-pub mod crypto {
-    use sha1::Digest as Sha1Digest;
-    use sha1::Sha1;
-    pub fn sha1(string: String) ->Vec<u8>{
-        let mut hash = Sha1::new();
-        
-        hash.update(string);
-        
-        hash.finalize().to_vec()
+// the stdin of builtins, read straight from fd 0 without a buffer,
+// so that a builtin takes no more than it reads, e.g. read leaves the next line to the next command.
+pub struct Stdin;
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = unsafe { libc::read(0, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+
+        match n < 0 {
+            true => Err(std::io::Error::last_os_error()),
+            false => Ok(n as usize),
+        }
     }
 }
+
+// writes the error as the message of the builtin, and returns the status of a failure.
+pub fn report(stderr: &mut dyn Write, err: Error) -> ExitStatus {
+    writeln!(stderr, "{err}").ok();
+    1
+}
@@ -0,0 +1,702 @@
+use super::alias::quote;
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use crate::variable::Value;
+use std::io::Read;
+use std::io::Write;
+
+pub struct Printf;
+
+impl Builtin for Printf {
+    fn name(&self) -> &str {
+        "printf"
+    }
+
+    fn help(&self) -> &str {
+        "printf [-v NAME] FORMAT [ARG ...]
+    prints the arguments by the format, which is used again while arguments are left.
+    the directives are %s %b %q %c %d %i %u %o %x %X %f %e %g %%, with flags, width and precision.
+    with -v, the output is set to the variable instead."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let mut args = args.into_iter().peekable();
+
+        let name = match args.next_if(|arg| arg == "-v") {
+            Some(_) => match args.next() {
+                Some(name) => Some(name),
+                None => return usage(stderr),
+            },
+            None => None,
+        };
+
+        args.next_if(|arg| arg == "--");
+
+        let format = match args.next() {
+            Some(format) => format,
+            None => return usage(stderr),
+        };
+
+        let (output, warnings) = match printf(&format, &args.collect::<Vec<String>>()) {
+            Ok(ok) => ok,
+            Err(err) => return super::report(stderr, err),
+        };
+
+        for warning in &warnings {
+            writeln!(stderr, "printf: {warning}").ok();
+        }
+
+        let result = match name {
+            Some(name) => state.assign(
+                name,
+                Value::String(String::from_utf8_lossy(&output).to_string()),
+            ),
+            None => stdout
+                .write_all(&output)
+                .map_err(|err| Error::new(ErrorKind::ExecutionFailed, err.to_string())),
+        };
+
+        match (result, warnings.is_empty()) {
+            (Err(err), _) => super::report(stderr, err),
+            (Ok(_), true) => 0,
+            (Ok(_), false) => 1,
+        }
+    }
+}
+
+fn usage(stderr: &mut dyn Write) -> ExitStatus {
+    super::report(
+        stderr,
+        Error::new(
+            ErrorKind::ExecutionFailed,
+            "printf: usage: printf [-v NAME] FORMAT [ARG ...]".to_owned(),
+        ),
+    )
+}
+
+// the output, and the arguments that were not numbers as warnings, which are printed as 0.
+// the format is used again while arguments are left, and \c stops everything.
+pub fn printf(format: &str, args: &[String]) -> Result<(Vec<u8>, Vec<String>)> {
+    let mut printer = Printer {
+        args,
+        index: 0,
+        output: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    loop {
+        let index = printer.index;
+
+        if !printer.print(format)? {
+            break;
+        }
+
+        // a format without directives, or every argument used.
+        if printer.index == index || printer.index >= args.len() {
+            break;
+        }
+    }
+
+    Ok((printer.output, printer.warnings))
+}
+
+struct Printer<'a> {
+    args: &'a [String],
+    index: usize,
+    output: Vec<u8>,
+    warnings: Vec<String>,
+}
+
+// %-+ #0 WIDTH .PRECISION CONVERSION
+#[derive(Default)]
+struct Spec {
+    is_left: bool,
+    is_plus: bool,
+    is_space: bool,
+    is_alternate: bool,
+    is_zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl<'a> Printer<'a> {
+    // once through the format. false when \c stopped it.
+    fn print(&mut self, format: &str) -> Result<bool> {
+        let chars = format.chars().collect::<Vec<char>>();
+
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => {
+                    let (len, stop) = unescape_at(&chars[i..], Escape::Format, &mut self.output);
+
+                    if stop {
+                        return Ok(false);
+                    }
+
+                    i += len;
+                }
+                '%' => {
+                    let (len, stop) = self.directive(&chars[i..])?;
+
+                    if stop {
+                        return Ok(false);
+                    }
+
+                    i += len;
+                }
+                ch => {
+                    let mut buf = [0; 4];
+                    self.output
+                        .extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                    i += 1;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    // the next argument, or nothing when they have run out.
+    fn arg(&mut self) -> Option<&'a String> {
+        let arg = self.args.get(self.index);
+
+        if arg.is_some() {
+            self.index += 1;
+        }
+
+        arg
+    }
+
+    fn integer_arg(&mut self) -> i64 {
+        match self.arg() {
+            Some(arg) => match integer(arg) {
+                Ok(n) => n,
+                Err(n) => {
+                    self.warnings.push(format!("{arg}: invalid number"));
+                    n
+                }
+            },
+            None => 0,
+        }
+    }
+
+    fn float_arg(&mut self) -> f64 {
+        match self.arg() {
+            Some(arg) => match float(arg) {
+                Some(n) => n,
+                None => {
+                    self.warnings.push(format!("{arg}: invalid number"));
+                    0.0
+                }
+            },
+            None => 0.0,
+        }
+    }
+
+    // a directive at the start of chars. returns the number of chars it took, and whether %b met \c.
+    fn directive(&mut self, chars: &[char]) -> Result<(usize, bool)> {
+        let mut spec = Spec::default();
+
+        let mut i = 1;
+
+        while let Some(ch) = chars.get(i) {
+            match ch {
+                '-' => spec.is_left = true,
+                '+' => spec.is_plus = true,
+                ' ' => spec.is_space = true,
+                '#' => spec.is_alternate = true,
+                '0' => spec.is_zero = true,
+                _ => break,
+            }
+            i += 1;
+        }
+
+        if chars.get(i) == Some(&'*') {
+            let width = self.integer_arg();
+
+            spec.is_left |= width < 0;
+            spec.width = width.unsigned_abs() as usize;
+
+            i += 1;
+        } else {
+            (spec.width, i) = digits(chars, i);
+        }
+
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+
+            if chars.get(i) == Some(&'*') {
+                // a negative precision is as if there were none.
+                let precision = self.integer_arg();
+                spec.precision = usize::try_from(precision).ok();
+
+                i += 1;
+            } else {
+                let precision;
+                (precision, i) = digits(chars, i);
+                spec.precision = Some(precision);
+            }
+        }
+
+        let conversion = match chars.get(i) {
+            Some(ch) => *ch,
+            None => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!(
+                    "printf: {}: missing conversion",
+                    chars.iter().collect::<String>()
+                ),
+            ))?,
+        };
+
+        let len = i + 1;
+
+        let (prefix, body, is_numeric) = match conversion {
+            '%' => {
+                self.output.push(b'%');
+                return Ok((len, false));
+            }
+            's' => {
+                let string = self.arg().cloned().unwrap_or_default();
+                (String::new(), truncate(string, spec.precision), false)
+            }
+            'q' => {
+                let string = shell_quote(self.arg().map(|arg| arg.as_str()).unwrap_or_default());
+                (String::new(), string, false)
+            }
+            'c' => {
+                let string = self
+                    .arg()
+                    .and_then(|arg| arg.chars().next())
+                    .map(|ch| ch.to_string())
+                    .unwrap_or_default();
+                (String::new(), string, false)
+            }
+            'b' => {
+                let string = self.arg().cloned().unwrap_or_default();
+
+                let mut bytes = Vec::new();
+                let stop = unescape(&string, Escape::Argument, &mut bytes);
+
+                if let Some(precision) = spec.precision {
+                    bytes.truncate(precision);
+                }
+
+                self.pad(&spec, "", &bytes, false);
+
+                return Ok((len, stop));
+            }
+            'd' | 'i' => {
+                let n = self.integer_arg();
+                let sign = sign(n < 0, &spec);
+                (
+                    sign,
+                    with_precision(n.unsigned_abs().to_string(), &mut spec),
+                    true,
+                )
+            }
+            'u' | 'o' | 'x' | 'X' => {
+                // negative numbers wrap, as in C.
+                let n = self.integer_arg() as u64;
+
+                let (digits, prefix) = match conversion {
+                    'u' => (n.to_string(), ""),
+                    'o' => (format!("{n:o}"), ""),
+                    'x' => (format!("{n:x}"), "0x"),
+                    _ => (format!("{n:X}"), "0X"),
+                };
+
+                let mut digits = with_precision(digits, &mut spec);
+
+                let prefix = match (spec.is_alternate, conversion) {
+                    (true, 'o') if !digits.starts_with('0') => {
+                        digits.insert(0, '0');
+                        ""
+                    }
+                    (true, 'x' | 'X') if n != 0 => prefix,
+                    _ => "",
+                };
+
+                (prefix.to_owned(), digits, true)
+            }
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' => {
+                let n = self.float_arg();
+                let sign = sign(n.is_sign_negative() && !n.is_nan(), &spec);
+
+                let body = match n.is_finite() {
+                    true => float_body(n.abs(), conversion, &spec),
+                    false => {
+                        // inf and nan are never padded with zeros.
+                        spec.is_zero = false;
+
+                        let body = if n.is_nan() { "nan" } else { "inf" };
+
+                        match conversion.is_uppercase() {
+                            true => body.to_uppercase(),
+                            false => body.to_owned(),
+                        }
+                    }
+                };
+
+                (sign, body, true)
+            }
+            _ => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!(
+                    "printf: %{}: invalid directive",
+                    chars[1..len].iter().collect::<String>()
+                ),
+            ))?,
+        };
+
+        self.pad(&spec, &prefix, body.as_bytes(), is_numeric);
+
+        Ok((len, false))
+    }
+
+    // the prefix and body filled to the width, with zeros between them for numbers with the 0 flag.
+    fn pad(&mut self, spec: &Spec, prefix: &str, body: &[u8], is_numeric: bool) {
+        let len = prefix.chars().count() + String::from_utf8_lossy(body).chars().count();
+        let fill = spec.width.saturating_sub(len);
+
+        // the 0 flag is ignored with -.
+        let is_zero = spec.is_zero && !spec.is_left && is_numeric;
+
+        if !spec.is_left && !is_zero {
+            self.output.extend(std::iter::repeat_n(b' ', fill));
+        }
+
+        self.output.extend_from_slice(prefix.as_bytes());
+
+        if is_zero {
+            self.output.extend(std::iter::repeat_n(b'0', fill));
+        }
+
+        self.output.extend_from_slice(body);
+
+        if spec.is_left {
+            self.output.extend(std::iter::repeat_n(b' ', fill));
+        }
+    }
+}
+
+// the number made of the digits from i, and where they end.
+fn digits(chars: &[char], mut i: usize) -> (usize, usize) {
+    let mut n: usize = 0;
+
+    while let Some(digit) = chars.get(i).and_then(|ch| ch.to_digit(10)) {
+        n = n.saturating_mul(10).saturating_add(digit as usize);
+        i += 1;
+    }
+
+    (n, i)
+}
+
+fn sign(is_negative: bool, spec: &Spec) -> String {
+    match (is_negative, spec.is_plus, spec.is_space) {
+        (true, _, _) => "-",
+        (false, true, _) => "+",
+        (false, false, true) => " ",
+        _ => "",
+    }
+    .to_owned()
+}
+
+fn truncate(string: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => string.chars().take(precision).collect(),
+        None => string,
+    }
+}
+
+// the precision of an integer is its least number of digits. the 0 flag is then ignored.
+fn with_precision(digits: String, spec: &mut Spec) -> String {
+    let precision = match spec.precision {
+        Some(precision) => precision,
+        None => return digits,
+    };
+
+    spec.is_zero = false;
+
+    match precision == 0 && digits == "0" {
+        true => String::new(),
+        false => format!("{digits:0>precision$}"),
+    }
+}
+
+// %f %e and %g of a finite, positive number.
+fn float_body(n: f64, conversion: char, spec: &Spec) -> String {
+    let precision = spec.precision.unwrap_or(6);
+
+    let body = match conversion {
+        'f' | 'F' => alternate(format!("{n:.precision$}"), spec),
+        'e' | 'E' => exponent(n, precision, spec),
+        _ => {
+            let precision = precision.max(1);
+
+            // the exponent it would have in %e, after rounding.
+            let x = format!("{n:.*e}", precision - 1)
+                .split_once('e')
+                .and_then(|(_, x)| x.parse::<i64>().ok())
+                .unwrap_or_default();
+
+            let body = match x < -4 || x >= precision as i64 {
+                true => exponent(n, precision - 1, spec),
+                false => alternate(format!("{n:.*}", (precision as i64 - 1 - x) as usize), spec),
+            };
+
+            // trailing zeros are removed, unless #.
+            match spec.is_alternate {
+                true => body,
+                false => strip_zeros(body),
+            }
+        }
+    };
+
+    match conversion.is_uppercase() {
+        true => body.to_uppercase(),
+        false => body,
+    }
+}
+
+// 1.500000e+02, as in C, where rust gives 1.500000e2.
+fn exponent(n: f64, precision: usize, spec: &Spec) -> String {
+    let string = format!("{n:.precision$e}");
+
+    let (mantissa, x) = string.split_once('e').unwrap_or((&string, "0"));
+    let x = x.parse::<i64>().unwrap_or_default();
+
+    let sign = if x < 0 { '-' } else { '+' };
+
+    format!(
+        "{}e{sign}{:02}",
+        alternate(mantissa.to_owned(), spec),
+        x.unsigned_abs()
+    )
+}
+
+// with #, there is always a decimal point.
+fn alternate(string: String, spec: &Spec) -> String {
+    match spec.is_alternate && !string.contains('.') {
+        true => string + ".",
+        false => string,
+    }
+}
+
+// 1.500000 to 1.5 and 2.000000e+00 to 2e+00
+fn strip_zeros(string: String) -> String {
+    let (mantissa, x) = match string.split_once('e') {
+        Some((mantissa, x)) => (mantissa.to_owned(), format!("e{x}")),
+        None => (string, String::new()),
+    };
+
+    let mantissa = match mantissa.contains('.') {
+        true => mantissa
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_owned(),
+        false => mantissa,
+    };
+
+    mantissa + &x
+}
+
+// 42, -42, 0x2a, 052 and 'c for the code of c. Err has what could be read.
+fn integer(arg: &str) -> std::result::Result<i64, i64> {
+    let arg = arg.trim();
+
+    if let Some(ch) = arg.strip_prefix(['\'', '"']) {
+        return Ok(ch.chars().next().map(|ch| ch as i64).unwrap_or_default());
+    }
+
+    let (is_negative, digits) = match arg.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, arg.strip_prefix('+').unwrap_or(arg)),
+    };
+
+    let (radix, digits) = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(digits) => (16, digits),
+        None if digits.len() > 1 && digits.starts_with('0') => (8, &digits[1..]),
+        None => (10, digits),
+    };
+
+    let n = match i64::from_str_radix(digits, radix) {
+        Ok(n) => Ok(n),
+        Err(_) => {
+            // the leading digits, e.g. 12 of 12abc.
+            let len = digits
+                .find(|ch: char| !ch.is_digit(radix))
+                .unwrap_or(digits.len());
+
+            Err(i64::from_str_radix(&digits[..len], radix).unwrap_or_default())
+        }
+    };
+
+    match is_negative {
+        true => n.map(|n| -n).map_err(|n| -n),
+        false => n,
+    }
+}
+
+fn float(arg: &str) -> Option<f64> {
+    let arg = arg.trim();
+
+    if arg.starts_with(['\'', '"']) {
+        return integer(arg).ok().map(|n| n as f64);
+    }
+
+    arg.parse::<f64>()
+        .ok()
+        .or_else(|| integer(arg).ok().map(|n| n as f64))
+}
+
+// %q, the string as the shell would read it back.
+fn shell_quote(string: &str) -> String {
+    let is_plain = !string.is_empty()
+        && string.chars().all(|ch| {
+            ch.is_alphanumeric()
+                || matches!(
+                    ch,
+                    '_' | '-' | '.' | '/' | ',' | ':' | '@' | '%' | '+' | '='
+                )
+        });
+
+    match is_plain {
+        true => string.to_owned(),
+        false => quote(string),
+    }
+}
+
+// where the escapes are, as each takes octal a little differently.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Escape {
+    // the format, \NNN
+    Format,
+    // %b, \0NNN and \NNN
+    Argument,
+    // echo -e, \0NNN only
+    Echo,
+}
+
+// the backslash escapes of the string, for echo -e and %b. true when \c stopped the output.
+pub fn unescape(string: &str, escape: Escape, output: &mut Vec<u8>) -> bool {
+    let chars = string.chars().collect::<Vec<char>>();
+
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            let (len, stop) = unescape_at(&chars[i..], escape, output);
+
+            if stop {
+                return true;
+            }
+
+            i += len;
+        } else {
+            let mut buf = [0; 4];
+            output.extend_from_slice(chars[i].encode_utf8(&mut buf).as_bytes());
+            i += 1;
+        }
+    }
+
+    false
+}
+
+// the escape at the start of chars, which begins with \.
+// returns the number of chars it took, and whether it was \c.
+fn unescape_at(chars: &[char], escape: Escape, output: &mut Vec<u8>) -> (usize, bool) {
+    let ch = match chars.get(1) {
+        Some(ch) => *ch,
+        None => {
+            output.push(b'\\');
+            return (1, false);
+        }
+    };
+
+    let byte = match ch {
+        'a' => 0x07,
+        'b' => 0x08,
+        'e' | 'E' => 0x1b,
+        'f' => 0x0c,
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        'v' => 0x0b,
+        '\\' => b'\\',
+        '"' if escape == Escape::Format => b'"',
+        '\'' if escape == Escape::Format => b'\'',
+        'c' => return (2, true),
+        '0'..='7' if escape != Escape::Echo || ch == '0' => {
+            let start = match (escape, ch) {
+                (Escape::Format, _) | (_, '1'..='7') => 1,
+                _ => 2,
+            };
+
+            let (n, len) = number(&chars[start..], 8, 3);
+
+            output.push(n as u8);
+            return (start + len, false);
+        }
+        'x' | 'u' | 'U' => {
+            let max = match ch {
+                'x' => 2,
+                'u' => 4,
+                _ => 8,
+            };
+
+            let (n, len) = number(&chars[2..], 16, max);
+
+            if len == 0 {
+                output.extend_from_slice(format!("\\{ch}").as_bytes());
+            } else if ch == 'x' {
+                output.push(n as u8);
+            } else {
+                let mut buf = [0; 4];
+                let ch = char::from_u32(n).unwrap_or(char::REPLACEMENT_CHARACTER);
+                output.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            }
+
+            return (2 + len, false);
+        }
+        // anything else is left as it is.
+        _ => {
+            let mut buf = [0; 4];
+            output.push(b'\\');
+            output.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            return (2, false);
+        }
+    };
+
+    output.push(byte);
+
+    (2, false)
+}
+
+// up to max digits of the radix, and how many there were.
+fn number(chars: &[char], radix: u32, max: usize) -> (u32, usize) {
+    let mut n: u32 = 0;
+    let mut len = 0;
+
+    while let Some(digit) = chars.get(len).and_then(|ch| ch.to_digit(radix)) {
+        if len == max {
+            break;
+        }
+
+        n = n.wrapping_mul(radix).wrapping_add(digit);
+        len += 1;
+    }
+
+    (n, len)
+}
@@ -0,0 +1,368 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use crate::terminal;
+use crate::variable::Value;
+use std::io;
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+pub struct Read;
+
+impl Builtin for Read {
+    fn name(&self) -> &str {
+        "read"
+    }
+
+    fn help(&self) -> &str {
+        "read [-rs] [-p PROMPT] [-t SECONDS] [-n COUNT] [-d DELIM] [-a NAME] [NAME ...]
+    reads a line from stdin and splits it by IFS into the names, the last taking the rest,
+    or into REPLY without a name. it fails at the end of the input, and after the timeout.
+    -r keeps backslashes, -s does not echo, -n stops after COUNT characters,
+    -d reads up to DELIM instead of a newline, and -a sets every field to the array NAME."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        stdin: &mut dyn io::Read,
+        _: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        match read(args, stdin, stderr, state) {
+            Ok(status) => status,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    is_raw: bool,
+    is_silent: bool,
+    prompt: Option<String>,
+    timeout: Option<Duration>,
+    count: Option<usize>,
+    delimiter: Option<char>,
+    array: Option<String>,
+    names: Vec<String>,
+}
+
+// why the reading stopped.
+enum End {
+    Delimiter,
+    Count,
+    Eof,
+    Timeout,
+}
+
+fn read(
+    args: Vec<String>,
+    stdin: &mut dyn io::Read,
+    stderr: &mut dyn Write,
+    state: &mut ShellState,
+) -> Result<ExitStatus> {
+    let options = parse_options(args)?;
+
+    // -t 0 only tells whether there is something to read.
+    if options.timeout == Some(Duration::ZERO) {
+        return Ok((!poll(Duration::ZERO)) as ExitStatus);
+    }
+
+    let is_tty = unsafe { libc::isatty(0) } == 1;
+
+    if let Some(prompt) = options.prompt.as_ref().filter(|_| is_tty) {
+        write!(stderr, "{prompt}").ok();
+        stderr.flush().ok();
+    }
+
+    // -s turns echo off, and -n returns without waiting for the end of the line.
+    let mut flags = 0;
+
+    if options.is_silent {
+        flags |= libc::ECHO;
+    }
+
+    if options.count.is_some() {
+        flags |= libc::ICANON;
+    }
+
+    let mode = match flags {
+        0 => None,
+        _ => terminal::Mode::unset(flags),
+    };
+
+    let (line, end) = read_line(stdin, &options);
+
+    drop(mode);
+
+    // the newline that was not echoed.
+    if options.is_silent && is_tty {
+        writeln!(stderr).ok();
+    }
+
+    let ifs = state.lookup("IFS").unwrap_or(" \t\n".to_owned());
+
+    match &options.array {
+        Some(name) => {
+            let fields = split(&line, &ifs, None);
+            state.assign(name.to_owned(), Value::Array(fields))?;
+        }
+        // REPLY takes the line as it is, without IFS.
+        None if options.names.is_empty() => {
+            let line = line.iter().map(|(ch, _)| *ch).collect::<String>();
+            state.assign("REPLY".to_owned(), Value::String(line))?;
+        }
+        None => {
+            let names = options.names;
+
+            let fields = split(&line, &ifs, Some(names.len()));
+
+            let mut fields = fields.into_iter();
+
+            for name in names {
+                state.assign(name, Value::String(fields.next().unwrap_or_default()))?;
+            }
+        }
+    }
+
+    // 1 at the end of the input, and above 128 after the timeout, as if by SIGALRM.
+    Ok(match end {
+        End::Delimiter | End::Count => 0,
+        End::Eof => 1,
+        End::Timeout => 128 + libc::SIGALRM,
+    })
+}
+
+// the options may be joined, e.g. -rs, and their values joined to them, e.g. -n1.
+fn parse_options(args: Vec<String>) -> Result<Options> {
+    let mut options = Options::default();
+
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+
+        if arg.len() < 2 || !arg.starts_with('-') {
+            options.names.push(arg);
+            break;
+        }
+
+        let mut chars = arg.chars().skip(1);
+
+        while let Some(ch) = chars.next() {
+            let mut value = || -> Result<String> {
+                let rest = chars.by_ref().collect::<String>();
+
+                match rest.is_empty() {
+                    true => args.next().ok_or(Error::new(
+                        ErrorKind::ExecutionFailed,
+                        format!("read: -{ch}: option requires an argument"),
+                    )),
+                    false => Ok(rest),
+                }
+            };
+
+            match ch {
+                'r' => options.is_raw = true,
+                's' => options.is_silent = true,
+                'p' => options.prompt = Some(value()?),
+                'a' => options.array = Some(value()?),
+                'd' => options.delimiter = Some(value()?.chars().next().unwrap_or('\0')),
+                'n' => {
+                    let string = value()?;
+
+                    options.count = Some(string.parse::<usize>().map_err(|_| {
+                        Error::new(
+                            ErrorKind::ExecutionFailed,
+                            format!("read: {string}: invalid count"),
+                        )
+                    })?)
+                }
+                't' => {
+                    let string = value()?;
+
+                    options.timeout = Some(
+                        string
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                            .ok_or(Error::new(
+                                ErrorKind::ExecutionFailed,
+                                format!("read: {string}: invalid timeout"),
+                            ))?,
+                    )
+                }
+                _ => Err(Error::new(
+                    ErrorKind::ExecutionFailed,
+                    format!("read: -{ch}: invalid option"),
+                ))?,
+            }
+        }
+    }
+
+    options.names.extend(args);
+
+    Ok(options)
+}
+
+// the characters up to the delimiter, each with whether a backslash made it literal.
+// without -r, a backslash before the newline joins the next line.
+fn read_line(stdin: &mut dyn io::Read, options: &Options) -> (Vec<(char, bool)>, End) {
+    let delimiter = options.delimiter.unwrap_or('\n');
+
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+
+    let mut line = Vec::new();
+
+    let mut is_escaped = false;
+
+    loop {
+        if options.count.is_some_and(|count| line.len() >= count) {
+            return (line, End::Count);
+        }
+
+        if let Some(deadline) = deadline {
+            if !poll(deadline.saturating_duration_since(Instant::now())) {
+                return (line, End::Timeout);
+            }
+        }
+
+        let ch = match read_char(stdin) {
+            Some(ch) => ch,
+            None => return (line, End::Eof),
+        };
+
+        if is_escaped {
+            is_escaped = false;
+
+            if ch != '\n' {
+                line.push((ch, true));
+            }
+
+            continue;
+        }
+
+        if ch == '\\' && !options.is_raw {
+            is_escaped = true;
+            continue;
+        }
+
+        if ch == delimiter {
+            return (line, End::Delimiter);
+        }
+
+        line.push((ch, false));
+    }
+}
+
+// a character of UTF-8, a byte at a time so that nothing after it is taken.
+fn read_char(stdin: &mut dyn io::Read) -> Option<char> {
+    let first = read_byte(stdin)?;
+
+    let len = match first {
+        0xf0..=0xf7 => 4,
+        0xe0..=0xef => 3,
+        0xc0..=0xdf => 2,
+        _ => 1,
+    };
+
+    let mut bytes = vec![first];
+
+    for _ in 1..len {
+        bytes.push(read_byte(stdin)?);
+    }
+
+    Some(
+        String::from_utf8_lossy(&bytes)
+            .chars()
+            .next()
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+    )
+}
+
+fn read_byte(stdin: &mut dyn io::Read) -> Option<u8> {
+    let mut byte = [0; 1];
+
+    loop {
+        match stdin.read(&mut byte) {
+            Ok(0) => return None,
+            Ok(_) => return Some(byte[0]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+    }
+}
+
+// whether stdin has something to read within the timeout.
+fn poll(timeout: Duration) -> bool {
+    let mut fd = libc::pollfd {
+        fd: 0,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    let millis = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+    unsafe { libc::poll(&mut fd, 1, millis) > 0 }
+}
+
+// the fields of the line split by IFS, as in sh. spaces, tabs and newlines of IFS are trimmed
+// and run together, while each other character of IFS separates a field of its own.
+// with a limit, the last field takes the rest of the line, separators and all.
+fn split(line: &[(char, bool)], ifs: &str, limit: Option<usize>) -> Vec<String> {
+    let is_separator = |&(ch, is_escaped): &(char, bool)| !is_escaped && ifs.contains(ch);
+    let is_space = |item: &(char, bool)| is_separator(item) && item.0.is_whitespace();
+
+    // the spaces of IFS at both ends.
+    let start = line
+        .iter()
+        .position(|item| !is_space(item))
+        .unwrap_or(line.len());
+    let end = line
+        .iter()
+        .rposition(|item| !is_space(item))
+        .map(|i| i + 1)
+        .unwrap_or(start);
+
+    let line = &line[start..end];
+
+    let mut fields = Vec::new();
+
+    let mut i = 0;
+
+    while i < line.len() {
+        if limit.is_some_and(|limit| fields.len() + 1 == limit) {
+            fields.push(line[i..].iter().map(|(ch, _)| *ch).collect());
+            return fields;
+        }
+
+        let start = i;
+
+        while i < line.len() && !is_separator(&line[i]) {
+            i += 1;
+        }
+
+        fields.push(line[start..i].iter().map(|(ch, _)| *ch).collect());
+
+        // the spaces around the separator, and one other character of IFS.
+        while i < line.len() && is_space(&line[i]) {
+            i += 1;
+        }
+
+        if i < line.len() && is_separator(&line[i]) && !is_space(&line[i]) {
+            i += 1;
+
+            while i < line.len() && is_space(&line[i]) {
+                i += 1;
+            }
+        }
+    }
+
+    fields
+}
@@ -0,0 +1,221 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell;
+use crate::shell::ShellState;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+// source and .
+pub struct Source {
+    pub name: &'static str,
+}
+
+impl Builtin for Source {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        match self.name {
+            "." => {
+                ". FILE [ARG ...]
+    the same as source."
+            }
+            _ => {
+                "source FILE [ARG ...]
+    runs the file in this shell, with the arguments as $1 ~ $9, $# and $@.
+    a FILE without / is looked for in the directories of NSH_PATH, then in the current directory.
+    variables made local in the file are gone when it returns."
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        _: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let mut args = args.into_iter();
+
+        let file = match args.next() {
+            Some(file) => file,
+            None => {
+                return super::report(
+                    stderr,
+                    Error::new(
+                        ErrorKind::ExecutionFailed,
+                        format!("{}: usage: {} FILE [ARG ...]", self.name, self.name),
+                    ),
+                )
+            }
+        };
+
+        // without arguments, the file sees those of where it is sourced from.
+        let args = args.collect::<Vec<String>>();
+        let args = (!args.is_empty()).then_some(args);
+
+        match source(state, &file, args) {
+            Ok(_) => state.status,
+            Err(err) => super::report(stderr, err),
+        }
+    }
+}
+
+// runs the file in the state, in a scope of its own. an error is reported with FILE:LINE of where it failed.
+pub fn source(state: &mut ShellState, file: &str, args: Option<Vec<String>>) -> Result<()> {
+    let path = find(state, file)?;
+
+    let canonical = path.canonicalize().unwrap_or(path.to_owned());
+
+    let is_same = |source: &PathBuf| source.canonicalize().ok().as_ref() == Some(&canonical);
+
+    if state.sources.iter().any(is_same) {
+        let chain = state
+            .sources
+            .iter()
+            .skip_while(|source| !is_same(source))
+            .chain([&path])
+            .map(|source| source.to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("source: {file}: include cycle: {}", chain.join(" -> ")),
+        ))?
+    }
+
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) => Err(Error::new(
+            ErrorKind::OpenFailed,
+            format!("source: {}: {err}", path.display()),
+        ))?,
+    };
+
+    let line = state.variable.line();
+
+    let saved = args.map(|args| {
+        let saved = state.variable.args().to_vec();
+        state.variable.set_args(args);
+        saved
+    });
+
+    state.sources.push(path.to_owned());
+    state.variable.push_scope();
+
+    let result = shell::eval(state, text);
+
+    let failed = state.variable.line();
+
+    state.variable.pop_scope();
+    state.sources.pop();
+
+    if let Some(args) = saved {
+        state.variable.set_args(args);
+    }
+
+    state.variable.set_line(line);
+
+    result.map_err(|err| {
+        Error::new(
+            err.kind().to_owned(),
+            format!("{}:{failed}: {err}", path.display()),
+        )
+    })
+}
+
+// FILE:LINE of the command being run in the file being sourced, if any.
+pub fn location(state: &ShellState) -> Option<String> {
+    state
+        .sources
+        .last()
+        .map(|path| format!("{}:{}", path.display(), state.variable.line()))
+}
+
+// the stderr of a builtin run from a sourced file, which puts FILE:LINE before each line written to it,
+// e.g. cd: /nonexistent: No such file or directory. a line that is flushed unfinished,
+// e.g. the prompt of read -p, is written as it is.
+pub struct Located<W: Write> {
+    inner: W,
+    location: Option<String>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> Located<W> {
+    pub fn new(inner: W, location: Option<String>) -> Self {
+        Self {
+            inner,
+            location,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for Located<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let location = match &self.location {
+            Some(location) => location,
+            None => return self.inner.write(buf),
+        };
+
+        self.buffer.extend_from_slice(buf);
+
+        while let Some(i) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line = self.buffer.drain(..=i).collect::<Vec<u8>>();
+
+            if line.len() > 1 {
+                write!(self.inner, "{location}: ")?;
+            }
+
+            self.inner.write_all(&line)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Located<W> {
+    fn drop(&mut self) {
+        self.flush().ok();
+    }
+}
+
+// a file with / is taken as it is, the others are looked for in NSH_PATH and then here.
+fn find(state: &ShellState, file: &str) -> Result<PathBuf> {
+    if file.contains('/') {
+        return Ok(PathBuf::from(file));
+    }
+
+    let dirs = state.lookup("NSH_PATH").unwrap_or_default();
+
+    let found = dirs
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| Path::new(dir).join(file))
+        .chain(env::current_dir().ok().map(|dir| dir.join(file)))
+        .find(|path| path.is_file());
+
+    match found {
+        Some(path) => Ok(path),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("source: {file}: not found"),
+        )),
+    }
+}
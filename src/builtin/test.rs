@@ -0,0 +1,346 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::shell::ShellState;
+use std::ffi::CString;
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::PermissionsExt;
+
+// test and [
+pub struct Test {
+    pub name: &'static str,
+}
+
+impl Builtin for Test {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        match self.name {
+            "[" => {
+                "[ EXPRESSION ]
+    the same as test, with ] as the last argument."
+            }
+            _ => {
+                "test EXPRESSION
+    evaluates the expression, and succeeds when it is true.
+    files: -e -f -d -r -w -x -s -L -h -b -c -p -S -u -g -k -O -G, -t FD, FILE -nt -ot -ef FILE.
+    strings: -n -z, = == != < >. integers: -eq -ne -lt -le -gt -ge.
+    ! EXPRESSION, EXPRESSION -a EXPRESSION, EXPRESSION -o EXPRESSION and ( EXPRESSION )."
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        mut args: Vec<String>,
+        _: &mut dyn Read,
+        _: &mut dyn Write,
+        stderr: &mut dyn Write,
+        _: &mut ShellState,
+    ) -> ExitStatus {
+        if self.name == "[" && args.pop().as_deref() != Some("]") {
+            writeln!(stderr, "[: missing ]").ok();
+            return 2;
+        }
+
+        // 1 when false, and 2 when the expression is wrong.
+        match test(&args) {
+            Ok(is_true) => (!is_true) as ExitStatus,
+            Err(err) => {
+                writeln!(stderr, "{}: {err}", self.name).ok();
+                2
+            }
+        }
+    }
+}
+
+// the expression as test takes it. up to 4 arguments, it is read by their number as in POSIX,
+// so that e.g. test -n and test = = = mean what they say.
+pub fn test(args: &[String]) -> Result<bool> {
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>();
+
+    match args.as_slice() {
+        [] => Ok(false),
+        [word] => Ok(!word.is_empty()),
+        ["!", word] => Ok(word.is_empty()),
+        [op, word] if is_unary(op) => unary(op, word),
+        [left, op, right] if is_binary(op) => binary(left, op, right),
+        [left, "-a", right] => Ok(!left.is_empty() && !right.is_empty()),
+        [left, "-o", right] => Ok(!left.is_empty() || !right.is_empty()),
+        ["!", left, right] => test_words(&[left, right]).map(|is_true| !is_true),
+        ["(", word, ")"] => Ok(!word.is_empty()),
+        ["!", _, _, _] => test_words(&args[1..]).map(|is_true| !is_true),
+        ["(", _, _, ")"] => test_words(&args[1..3]),
+        _ => {
+            let mut parser = Parser { args: &args, i: 0 };
+
+            let is_true = parser.or()?;
+
+            match parser.args.get(parser.i) {
+                Some(arg) => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("{arg}: unexpected argument"),
+                )),
+                None => Ok(is_true),
+            }
+        }
+    }
+}
+
+fn test_words(args: &[&str]) -> Result<bool> {
+    test(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>())
+}
+
+// EXPRESSION -o EXPRESSION binds looser than -a, and ! tighter than both.
+struct Parser<'a> {
+    args: &'a [&'a str],
+    i: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self, n: usize) -> Option<&'a str> {
+        self.args.get(self.i + n).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        match self.peek(0) {
+            Some(arg) => {
+                self.i += 1;
+                Ok(arg)
+            }
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "argument expected".to_owned(),
+            )),
+        }
+    }
+
+    fn or(&mut self) -> Result<bool> {
+        let mut is_true = self.and()?;
+
+        while self.peek(0) == Some("-o") {
+            self.i += 1;
+            is_true = self.and()? || is_true;
+        }
+
+        Ok(is_true)
+    }
+
+    fn and(&mut self) -> Result<bool> {
+        let mut is_true = self.not()?;
+
+        while self.peek(0) == Some("-a") {
+            self.i += 1;
+            is_true = self.not()? && is_true;
+        }
+
+        Ok(is_true)
+    }
+
+    fn not(&mut self) -> Result<bool> {
+        match self.peek(0) {
+            Some("!") if self.peek(1).is_some() => {
+                self.i += 1;
+                Ok(!self.not()?)
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<bool> {
+        // a binary operator comes before everything else, e.g. ( = ( is a comparison.
+        if let (Some(op), Some(_)) = (self.peek(1), self.peek(2)) {
+            if is_binary(op) {
+                let left = self.next()?;
+                self.i += 1;
+                let right = self.next()?;
+
+                return binary(left, op, right);
+            }
+        }
+
+        let arg = self.next()?;
+
+        match arg {
+            "(" => {
+                let is_true = self.or()?;
+
+                match self.next()? {
+                    ")" => Ok(is_true),
+                    arg => Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        format!("{arg}: ) expected"),
+                    )),
+                }
+            }
+            op if is_unary(op) && self.peek(0).is_some() => unary(op, self.next()?),
+            word => Ok(!word.is_empty()),
+        }
+    }
+}
+
+pub fn is_unary(op: &str) -> bool {
+    matches!(
+        op,
+        "-e" | "-f"
+            | "-d"
+            | "-r"
+            | "-w"
+            | "-x"
+            | "-s"
+            | "-L"
+            | "-h"
+            | "-b"
+            | "-c"
+            | "-p"
+            | "-S"
+            | "-u"
+            | "-g"
+            | "-k"
+            | "-O"
+            | "-G"
+            | "-t"
+            | "-n"
+            | "-z"
+    )
+}
+
+pub fn is_binary(op: &str) -> bool {
+    matches!(
+        op,
+        "=" | "=="
+            | "!="
+            | "<"
+            | ">"
+            | "-eq"
+            | "-ne"
+            | "-lt"
+            | "-le"
+            | "-gt"
+            | "-ge"
+            | "-nt"
+            | "-ot"
+            | "-ef"
+    )
+}
+
+// -OP WORD
+pub fn unary(op: &str, word: &str) -> Result<bool> {
+    match op {
+        "-n" => return Ok(!word.is_empty()),
+        "-z" => return Ok(word.is_empty()),
+        "-t" => {
+            let fd = integer(word)?;
+            return Ok(unsafe { libc::isatty(fd as i32) } == 1);
+        }
+        "-r" => return Ok(access(word, libc::R_OK)),
+        "-w" => return Ok(access(word, libc::W_OK)),
+        "-x" => return Ok(access(word, libc::X_OK)),
+        _ => {}
+    }
+
+    // -L and -h look at the link itself, the others at what it points to.
+    let metadata = match op {
+        "-L" | "-h" => fs::symlink_metadata(word),
+        _ => fs::metadata(word),
+    };
+
+    let metadata = match metadata {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(false),
+    };
+
+    let file_type = metadata.file_type();
+    let mode = metadata.permissions().mode();
+
+    Ok(match op {
+        "-e" => true,
+        "-f" => file_type.is_file(),
+        "-d" => file_type.is_dir(),
+        "-s" => metadata.len() > 0,
+        "-L" | "-h" => file_type.is_symlink(),
+        "-b" => file_type.is_block_device(),
+        "-c" => file_type.is_char_device(),
+        "-p" => file_type.is_fifo(),
+        "-S" => file_type.is_socket(),
+        "-u" => mode & libc::S_ISUID != 0,
+        "-g" => mode & libc::S_ISGID != 0,
+        "-k" => mode & libc::S_ISVTX != 0,
+        "-O" => metadata.uid() == unsafe { libc::geteuid() },
+        "-G" => metadata.gid() == unsafe { libc::getegid() },
+        _ => Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("{op}: unary operator expected"),
+        ))?,
+    })
+}
+
+// WORD OP WORD
+pub fn binary(left: &str, op: &str, right: &str) -> Result<bool> {
+    Ok(match op {
+        "=" | "==" => left == right,
+        "!=" => left != right,
+        "<" => left < right,
+        ">" => left > right,
+        "-eq" => integer(left)? == integer(right)?,
+        "-ne" => integer(left)? != integer(right)?,
+        "-lt" => integer(left)? < integer(right)?,
+        "-le" => integer(left)? <= integer(right)?,
+        "-gt" => integer(left)? > integer(right)?,
+        "-ge" => integer(left)? >= integer(right)?,
+        // a file that exists is newer than one that does not.
+        "-nt" | "-ot" => {
+            let (left, right) = (modified(left), modified(right));
+
+            let (newer, older) = match op {
+                "-nt" => (left, right),
+                _ => (right, left),
+            };
+
+            match (newer, older) {
+                (Some(newer), Some(older)) => newer > older,
+                (Some(_), None) => true,
+                _ => false,
+            }
+        }
+        "-ef" => match (fs::metadata(left), fs::metadata(right)) {
+            (Ok(left), Ok(right)) => left.dev() == right.dev() && left.ino() == right.ino(),
+            _ => false,
+        },
+        _ => Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("{op}: binary operator expected"),
+        ))?,
+    })
+}
+
+pub fn integer(word: &str) -> Result<i64> {
+    match word.trim().parse::<i64>() {
+        Ok(n) => Ok(n),
+        Err(_) => Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("{word}: integer expression expected"),
+        )),
+    }
+}
+
+// the modification time in nanoseconds.
+fn modified(path: &str) -> Option<i128> {
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.mtime() as i128 * 1_000_000_000 + metadata.mtime_nsec() as i128)
+}
+
+// whether the shell may read, write or run the file, as access(2) says.
+fn access(path: &str, mode: i32) -> bool {
+    match CString::new(path) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), mode) == 0 },
+        Err(_) => false,
+    }
+}
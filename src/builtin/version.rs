@@ -0,0 +1,90 @@
+use super::Builtin;
+use super::ExitStatus;
+use crate::error::*;
+use crate::manifest;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+pub struct Version;
+
+impl Builtin for Version {
+    fn name(&self) -> &str {
+        "version"
+    }
+
+    fn help(&self) -> &str {
+        "version [--json]
+    shows the version of nsh, the commit and the date it was built, and its authors."
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        _: &mut ShellState,
+    ) -> ExitStatus {
+        let output = match args.first().map(|arg| arg.as_str()) {
+            None => format!(
+                "{}\nauthors: {}",
+                manifest::describe(),
+                manifest::authors().join(", ")
+            ),
+            Some("--json") => json(),
+            Some(arg) => {
+                return super::report(
+                    stderr,
+                    Error::new(
+                        ErrorKind::ExecutionFailed,
+                        format!("version: unknown option: {arg}"),
+                    ),
+                )
+            }
+        };
+
+        match writeln!(stdout, "{output}") {
+            Ok(_) => 0,
+            Err(err) => super::report(
+                stderr,
+                Error::new(ErrorKind::ExecutionFailed, err.to_string()),
+            ),
+        }
+    }
+}
+
+// {"name":"nsh","version":"1.0.0",...} on one line.
+fn json() -> String {
+    let authors = manifest::authors()
+        .iter()
+        .map(|author| quote(author))
+        .collect::<Vec<String>>()
+        .join(",");
+
+    format!(
+        "{{\"name\":{},\"version\":{},\"authors\":[{authors}],\"commit\":{},\"build_date\":{}}}",
+        quote(manifest::name()),
+        quote(manifest::version()),
+        quote(manifest::commit()),
+        quote(manifest::build_date()),
+    )
+}
+
+// a JSON string.
+fn quote(string: &str) -> String {
+    let mut buffer = String::from('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            c if (c as u32) < 0x20 => buffer.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buffer.push(c),
+        }
+    }
+
+    buffer.push('"');
+    buffer
+}
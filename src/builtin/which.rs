@@ -0,0 +1,105 @@
+use super::command;
+use super::command::Resolution;
+use super::Builtin;
+use super::ExitStatus;
+use crate::shell::ShellState;
+use std::io::Read;
+use std::io::Write;
+
+// type and which
+pub struct Which {
+    pub name: &'static str,
+}
+
+impl Builtin for Which {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn help(&self) -> &str {
+        match self.name {
+            "which" => {
+                "which [-a] NAME ...
+    shows the file in PATH that runs for each name, or with -a, every one of them.
+    it fails when a name is not found."
+            }
+            _ => {
+                "type [-a | -t | -p] NAME ...
+    shows what each name runs: an alias, a keyword, a builtin, or a file in PATH, hashed or not.
+    -a shows all of them in the order they are looked for, -t only the kind of each,
+    and -p only the file."
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        args: Vec<String>,
+        _: &mut dyn Read,
+        stdout: &mut dyn Write,
+        stderr: &mut dyn Write,
+        state: &mut ShellState,
+    ) -> ExitStatus {
+        let mut is_all = false;
+        let mut is_kind = false;
+        let mut is_path = self.name == "which";
+
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if arg == "--" {
+                break;
+            }
+
+            for ch in arg.chars().skip(1) {
+                match (ch, self.name) {
+                    ('a', _) => is_all = true,
+                    ('t', "type") => is_kind = true,
+                    ('p', "type") => is_path = true,
+                    _ => {
+                        writeln!(stderr, "{}: -{ch}: invalid option", self.name).ok();
+                        return 2;
+                    }
+                }
+            }
+        }
+
+        let mut status = 0;
+
+        for name in args {
+            let resolutions = match (is_all, is_path) {
+                (true, true) => command::find_all_in_path(&command::search_path(state), &name)
+                    .into_iter()
+                    .map(Resolution::File)
+                    .collect(),
+                (true, false) => command::resolve_all(state, &name),
+                (false, true) => command::find(state, &name)
+                    .map(Resolution::File)
+                    .into_iter()
+                    .collect(),
+                (false, false) => command::resolve(state, &name).into_iter().collect(),
+            };
+
+            if resolutions.is_empty() {
+                // type -t and -p say nothing of a name that is not found.
+                if !is_kind && (!is_path || self.name == "which") {
+                    writeln!(stderr, "{}: {name}: not found", self.name).ok();
+                }
+
+                status = 1;
+            }
+
+            for resolution in resolutions {
+                let line = match (is_kind, is_path) {
+                    (true, _) => resolution.kind().to_owned(),
+                    (false, true) => resolution.short(&name),
+                    (false, false) => resolution.describe(&name),
+                };
+
+                writeln!(stdout, "{line}").ok();
+            }
+        }
+
+        status
+    }
+}
@@ -10,6 +10,8 @@ pub enum ErrorKind {
     NotFound,
     ExecutionFailed,
     WrongSyntax,
+    DivisionByZero,
+    Overflow,
 }
 #[derive(Debug, Clone)]
 pub struct Error {
@@ -19,10 +21,7 @@ pub struct Error {
 
 impl Error {
     pub fn new(kind: ErrorKind, message: String) -> Self {
-        Self {
-            kind: kind,
-            message: message,
-        }
+        Self { kind, message }
     }
 
     pub fn message(&self) -> &str {
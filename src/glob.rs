@@ -0,0 +1,137 @@
+// shell glob patterns, e.g. the patterns of case.
+// * matches any string, ? any single character, [abc] [a-z] one of the set and [!abc] [^abc] none of it.
+// a backslash makes the next character literal.
+pub fn is_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let text = text.chars().collect::<Vec<char>>();
+
+    let (mut p, mut t) = (0, 0);
+
+    // where the last * was seen, and the text position it is tried from.
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    star = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+
+                '?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+
+                '[' => {
+                    if let Some((is_match, len)) = match_class(&pattern[p..], text[t]) {
+                        if is_match {
+                            p += len;
+                            t += 1;
+                            continue;
+                        }
+                    } else if text[t] == '[' {
+                        // an unclosed [ is just a character.
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+
+                '\\' if p + 1 < pattern.len() => {
+                    if pattern[p + 1] == text[t] {
+                        p += 2;
+                        t += 1;
+                        continue;
+                    }
+                }
+
+                ch => {
+                    if ch == text[t] {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        // backtracks so that the last * takes one more character.
+        match star {
+            Some((star_p, star_t)) => {
+                star = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+// matches ch against the [...] at the start of the pattern.
+// returns whether it matched and the length of the class, or None when the class is not closed.
+fn match_class(pattern: &[char], ch: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+
+    let is_negated = matches!(pattern.get(i), Some('!' | '^'));
+
+    if is_negated {
+        i += 1;
+    }
+
+    let mut is_match = false;
+
+    // a ] right after [ or [! is a member, not the end.
+    let mut is_first = true;
+
+    loop {
+        let mut start = *pattern.get(i)?;
+
+        if start == ']' && !is_first {
+            return Some((is_match != is_negated, i + 1));
+        }
+
+        is_first = false;
+
+        if start == '\\' {
+            i += 1;
+            start = *pattern.get(i)?;
+        }
+
+        if pattern.get(i + 1) == Some(&'-') && matches!(pattern.get(i + 2), Some(end) if *end != ']')
+        {
+            let end = pattern[i + 2];
+
+            if start <= ch && ch <= end {
+                is_match = true;
+            }
+
+            i += 3;
+        } else {
+            if start == ch {
+                is_match = true;
+            }
+
+            i += 1;
+        }
+    }
+}
+
+// makes every character of the string literal in a pattern.
+pub fn escape(string: &str) -> String {
+    let mut buffer = String::new();
+
+    for ch in string.chars() {
+        if matches!(ch, '*' | '?' | '[' | ']' | '\\') {
+            buffer.push('\\');
+        }
+
+        buffer.push(ch);
+    }
+
+    buffer
+}
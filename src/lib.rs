@@ -1,4 +1,8 @@
+pub mod alias;
+pub mod arithmetic;
+pub mod glob;
 pub mod parser;
+pub mod pattern;
 pub mod error;
 pub mod builtin;
 pub mod manifest;
@@ -6,4 +10,5 @@ pub mod ansi;
 pub mod terminal;
 pub mod shell;
 pub mod profile;
+pub mod redirect;
 pub mod variable;
\ No newline at end of file
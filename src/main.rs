@@ -1,7 +1,13 @@
+use std::env;
 use std::io::stderr;
 use std::io::Write;
 
 fn main() {
+    if let Some("--version" | "-V") = env::args().nth(1).as_deref() {
+        println!("{}", nsh::manifest::describe());
+        return;
+    }
+
     match nsh::shell::Shell::new().initialize() {
         Ok(ok) => ok.repl(),
         Err(err) => stderr()
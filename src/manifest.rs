@@ -1,11 +1,36 @@
-pub fn name()->&'static str{
-    "nsh"
+// what the shell knows about its own build, from Cargo and build.rs.
+
+pub fn name() -> &'static str {
+    env!("CARGO_PKG_NAME")
+}
+
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+// the authors, separated by colons as Cargo gives them.
+pub fn author() -> &'static str {
+    env!("CARGO_PKG_AUTHORS")
 }
 
-pub fn version()->&'static str{    
-    "1.0.0"
+pub fn authors() -> Vec<&'static str> {
+    author()
+        .split(':')
+        .filter(|author| !author.is_empty())
+        .collect()
 }
 
-pub fn author()->&'static str{
-    "flucium"
-}
\ No newline at end of file
+// the short hash of the commit it was built from, or unknown.
+pub fn commit() -> &'static str {
+    option_env!("NSH_GIT_COMMIT").unwrap_or("unknown")
+}
+
+// YYYY-MM-DD
+pub fn build_date() -> &'static str {
+    option_env!("NSH_BUILD_DATE").unwrap_or("unknown")
+}
+
+// nsh 1.0.0 (abcdef0 2024-01-01), for nsh --version and the version builtin.
+pub fn describe() -> String {
+    format!("{} {} ({} {})", name(), version(), commit(), build_date())
+}
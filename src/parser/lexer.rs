@@ -1,3 +1,4 @@
+use crate::alias::Alias;
 use crate::parser::token::Token;
 use std::collections::VecDeque;
 use std::mem;
@@ -6,17 +7,52 @@ use std::mem;
 pub struct Lexer {
     input: VecDeque<char>,
     peek_token: Option<Token>,
+    is_terminated: bool,
+    // whether the next word starts a command, where keywords such as let are recognized.
+    is_head: bool,
+    // whether whitespace came before the last token that was read.
+    is_spaced: bool,
+    // the offset where each line of the input starts, and the length of the input.
+    lines: Vec<usize>,
+    size: usize,
+    // the line of the token returned last, and of the token read ahead.
+    line: usize,
+    peek_line: usize,
+    // the length of the input left after the text of the alias being read, which keeps the line of its word.
+    alias_rest: Option<usize>,
+    // the aliases that are expanded where a command starts.
+    alias: Alias,
 }
 
 impl Iterator for Lexer {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut token = self.peek_token.take().or_else(|| self.pop_front());
+        let mut token = match self.peek_token.take() {
+            Some(token) => Some(token),
+            None => {
+                let token = self.pop_front();
+                self.peek_line = self.line;
+                token
+            }
+        };
+
+        let line = self.peek_line;
 
-        self.peek_token = self.pop_front();
+        // only a word is read ahead, to see if the = of NAME=val follows it.
+        // the end of a command line is not, so the aliases can change before the next one is read.
+        if matches!(token, Some(Token::String(_))) {
+            self.peek_token = self.pop_front();
+            self.peek_line = self.line;
+        }
 
-        if matches!(self.peek_token,Some(Token::Equal)){
+        self.line = line;
+
+        // NAME=val is read as =NAME val, when the = is right after the name.
+        if matches!(self.peek_token, Some(Token::Equal))
+            && !self.is_spaced
+            && matches!(token, Some(Token::String(_)))
+        {
             mem::swap(&mut token, &mut self.peek_token);
         }
 
@@ -26,21 +62,100 @@ impl Iterator for Lexer {
 
 impl Lexer {
     pub fn new(input: VecDeque<char>) -> Self {
+        let mut lines = vec![0];
+
+        for (i, ch) in input.iter().enumerate() {
+            if *ch == '\n' {
+                lines.push(i + 1);
+            }
+        }
+
         Self {
-            input: input,
+            size: input.len(),
+            input,
             peek_token: None,
+            is_terminated: true,
+            is_head: true,
+            is_spaced: false,
+            lines,
+            line: 1,
+            peek_line: 1,
+            alias_rest: None,
+            alias: Alias::new(),
         }
     }
+
+    pub fn set_alias(&mut self, alias: Alias) -> &mut Self {
+        self.alias = alias;
+        self
+    }
+
+    pub fn alias(&self) -> &Alias {
+        &self.alias
+    }
+
+    // the line of the token returned last, counted from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // false if the input ended while a here-document was still waiting for its delimiter.
+    pub fn is_terminated(&self) -> bool {
+        self.is_terminated
+    }
+
     fn pop_front(&mut self) -> Option<Token> {
+        let token = self.read_token();
+
+        self.is_head = matches!(
+            token,
+            Some(
+                Token::Semicolon
+                    | Token::Pipe
+                    | Token::And
+                    | Token::Or
+                    | Token::Ampersand
+                    | Token::LParen
+                    | Token::LBrace
+                    | Token::RBrace
+                    | Token::RParen
+                    | Token::Do
+                    | Token::SemicolonSemicolon
+                    | Token::SemicolonAnd
+                    | Token::SemicolonSemicolonAnd
+            )
+        );
+
+        token
+    }
+
+    fn read_token(&mut self) -> Option<Token> {
+        self.is_spaced = false;
+
+        // the first word of an expanded alias is not expanded again.
+        let mut is_aliased = false;
+
         while let Some(ch) = self.input.pop_front() {
+            if ch == '\n' {
+                return Some(Token::Semicolon);
+            }
+
             if ch.is_whitespace() {
+                self.is_spaced = true;
                 continue;
             }
 
+            let offset = self.size.saturating_sub(self.input.len() + 1);
+
+            if !self.is_alias_text() {
+                self.line = self.lines.partition_point(|start| *start <= offset);
+            }
+
             match ch {
                 '#' => {
                     while let Some(ch) = self.input.pop_front() {
                         if ch == '\n' {
+                            self.input.push_front(ch);
                             break;
                         }
                     }
@@ -55,7 +170,7 @@ impl Lexer {
                         Ok(number) => {
                             let front_ch = self.input.front().unwrap_or(&' ');
 
-                            if front_ch.is_whitespace() || matches!(front_ch, '>' | '<') == false {
+                            if front_ch.is_whitespace() || !matches!(front_ch, '>' | '<') {
                                 return Some(Token::String(string));
                             } else {
                                 return Some(Token::FD(number));
@@ -66,14 +181,62 @@ impl Lexer {
                     }
                 }
 
-                '|' => return Some(Token::Pipe),
+                '|' => {
+                    if self.input.front() == Some(&'|') {
+                        self.input.pop_front();
+                        return Some(Token::Or);
+                    }
+
+                    return Some(Token::Pipe);
+                }
+
+                '(' => {
+                    // (( expression ))
+                    if self.is_head && self.input.front() == Some(&'(') {
+                        self.input.pop_front();
+                        return Some(Token::Let(self.read_arithmetic()));
+                    }
+
+                    return Some(Token::LParen);
+                }
 
-                ';' => return Some(Token::Semicolon),
+                ')' => return Some(Token::RParen),
+
+                ';' => {
+                    // ;; ;& ;;& end an item of case.
+                    if self.input.front() == Some(&'&') {
+                        self.input.pop_front();
+                        return Some(Token::SemicolonAnd);
+                    }
+
+                    if self.input.front() != Some(&';') {
+                        return Some(Token::Semicolon);
+                    }
+
+                    self.input.pop_front();
+
+                    if self.input.front() == Some(&'&') {
+                        self.input.pop_front();
+                        return Some(Token::SemicolonSemicolonAnd);
+                    }
+
+                    return Some(Token::SemicolonSemicolon);
+                }
 
                 '&' => {
-                    if self.input.front().unwrap_or(&' ').is_whitespace() == false {
+                    if self.input.front() == Some(&'&') {
+                        self.input.pop_front();
+                        return Some(Token::And);
+                    }
+
+                    if !self.input.front().unwrap_or(&' ').is_whitespace() {
                         let mut string = self.read_string(false);
 
+                        // &- closes the file descriptor, e.g. 2>&-
+                        if string == "-" {
+                            return Some(Token::FD(-1));
+                        }
+
                         match string.parse::<i32>() {
                             Ok(number) => return Some(Token::FD(number)),
                             Err(_) => {
@@ -87,36 +250,145 @@ impl Lexer {
                     return Some(Token::Ampersand);
                 }
 
-                '>' => return Some(Token::Gt),
+                '>' => {
+                    if self.input.front() == Some(&'(') {
+                        self.input.pop_front();
+                        return Some(Token::OutputSubstitution(self.read_substitution()));
+                    }
+
+                    if self.input.front() == Some(&'>') {
+                        self.input.pop_front();
+                        return Some(Token::GtGt);
+                    }
+
+                    return Some(Token::Gt);
+                }
+
+                '<' => {
+                    if self.input.front() == Some(&'(') {
+                        self.input.pop_front();
+                        return Some(Token::InputSubstitution(self.read_substitution()));
+                    }
+
+                    if self.input.front() != Some(&'<') {
+                        return Some(Token::Lt);
+                    }
+
+                    self.input.pop_front();
+
+                    if self.input.front() == Some(&'<') {
+                        self.input.pop_front();
+                        return Some(Token::LtLtLt);
+                    }
+
+                    let is_strip = self.input.front() == Some(&'-');
+
+                    if is_strip {
+                        self.input.pop_front();
+                    }
+
+                    return Some(self.read_here_doc(is_strip));
+                }
+
+                '=' => {
+                    // = and == of their own are words, e.g. [ a = b ]
+                    if self
+                        .input
+                        .front()
+                        .is_none_or(|ch| ch.is_whitespace() || *ch == '=')
+                    {
+                        let mut string = String::from(ch);
+
+                        while let Some(ch) = self.input.pop_front() {
+                            if ch != '=' {
+                                self.input.push_front(ch);
+                                break;
+                            }
+
+                            string.push(ch);
+                        }
 
-                '<' => return Some(Token::Lt),
+                        return Some(Token::String(string));
+                    }
 
-                '=' => return Some(Token::Equal),
+                    return Some(Token::Equal);
+                }
 
                 // '$' => return Some(Token::Variable(self.read_string(false))),
                 '$' => {
+                    // $(( expression ))
+                    if self.input.front() == Some(&'(') && self.input.get(1) == Some(&'(') {
+                        self.input.drain(..2);
+                        return Some(Token::Arithmetic(self.read_arithmetic()));
+                    }
+
                     let front_ch = self.input.front().unwrap_or(&' ');
 
                     if front_ch.is_whitespace() {
                         return Some(Token::String("$".to_owned()));
-                    } else {
-                        return Some(Token::Variable(self.read_string(front_ch == &'"')));
+                    }
+
+                    self.input.push_front(ch);
+
+                    let string = self.read_string(false);
+
+                    // a reference on its own, e.g. $NAME, $xs[1] or ${NAME}, can expand to several words.
+                    // anything else is a string that is expanded as one word, e.g. $HOME/bin
+                    match string.strip_prefix('$').filter(|key| is_reference(key)) {
+                        Some(key) => return Some(Token::Variable(key.to_owned())),
+                        None => return Some(Token::String(string)),
                     }
                 }
 
                 '"' => return Some(Token::String(self.read_string(true))),
 
                 _ => {
-                    let mut string = String::from(ch);
+                    self.input.push_front(ch);
 
-                    string.push_str(&self.read_string(false));
-                    
-                    // if string.to_lowercase() == "include" {
-                    //     return Some(Token::Include);
-                    // } else {
-                    //     return Some(Token::String(string));
-                    // }
-                    return Some(Token::String(string));
+                    let string = self.read_string(false);
+
+                    // the alias is put back into the input in place of the word, except for NAME=val.
+                    if self.is_head && !is_aliased && self.input.front() != Some(&'=') {
+                        if let Some(text) = self.alias.expand(&string) {
+                            if !self.is_alias_text() {
+                                self.alias_rest = Some(self.input.len());
+                            }
+
+                            for ch in text.chars().rev() {
+                                self.input.push_front(ch);
+                            }
+
+                            is_aliased = true;
+                            continue;
+                        }
+                    }
+
+                    // include FILE, which runs the file as source does.
+                    if self.is_head && string == "include" {
+                        return Some(Token::Include);
+                    }
+
+                    // let a=1 b+=2, every word is an expression of its own.
+                    if self.is_head && string == "let" {
+                        return Some(Token::Let(self.read_let()));
+                    }
+
+                    if self.is_head && string == "[[" {
+                        return Some(Token::Conditional(self.read_conditional()));
+                    }
+
+                    // { and } are only reserved as words of their own, like in sh.
+                    // case, esac, for, do and done only where a command starts.
+                    match string.as_str() {
+                        "case" if self.is_head => return Some(Token::Case),
+                        "esac" if self.is_head => return Some(Token::Esac),
+                        "for" if self.is_head => return Some(Token::For),
+                        "do" if self.is_head => return Some(Token::Do),
+                        "done" if self.is_head => return Some(Token::Done),
+                        "{" => return Some(Token::LBrace),
+                        "}" => return Some(Token::RBrace),
+                        _ => return Some(Token::String(string)),
+                    }
                 }
             }
         }
@@ -124,6 +396,300 @@ impl Lexer {
         None
     }
 
+    // whether the char read last came from the text of an alias.
+    fn is_alias_text(&self) -> bool {
+        self.alias_rest.is_some_and(|rest| self.input.len() >= rest)
+    }
+
+    // <<DELIMITER and <<-DELIMITER.
+    // the body is cut out of the lines that follow the current one,
+    // so that the rest of the current line is still lexed as usual.
+    fn read_here_doc(&mut self, is_strip: bool) -> Token {
+        while matches!(self.input.front(), Some(' ' | '\t')) {
+            self.input.pop_front();
+        }
+
+        let mut delimiter = String::new();
+
+        // a quoted delimiter, even partly, turns off the expansion of the body.
+        let mut is_quoted = false;
+
+        while let Some(ch) = self.input.pop_front() {
+            match ch {
+                '\'' | '"' => {
+                    is_quoted = true;
+
+                    while let Some(quoted_ch) = self.input.pop_front() {
+                        if quoted_ch == ch {
+                            break;
+                        }
+
+                        delimiter.push(quoted_ch);
+                    }
+                }
+
+                '\\' => {
+                    is_quoted = true;
+
+                    if let Some(ch) = self.input.pop_front() {
+                        delimiter.push(ch);
+                    }
+                }
+
+                _ => {
+                    if ch.is_whitespace() || matches!(ch, ';' | '|' | '&' | '<' | '>') {
+                        self.input.push_front(ch);
+                        break;
+                    }
+
+                    delimiter.push(ch);
+                }
+            }
+        }
+
+        let mut rest = match self.input.iter().position(|ch| *ch == '\n') {
+            Some(position) => self.input.split_off(position + 1),
+            None => VecDeque::new(),
+        };
+
+        let mut body = String::new();
+
+        loop {
+            if rest.is_empty() {
+                self.is_terminated = false;
+                break;
+            }
+
+            let mut line = String::new();
+
+            while let Some(ch) = rest.pop_front() {
+                if ch == '\n' {
+                    break;
+                }
+
+                line.push(ch);
+            }
+
+            if is_strip {
+                line = line.trim_start_matches('\t').to_owned();
+            }
+
+            if line == delimiter {
+                break;
+            }
+
+            body.push_str(&line);
+            body.push('\n');
+        }
+
+        self.input.append(&mut rest);
+
+        Token::HereDoc(body, !is_quoted)
+    }
+
+    // the source up to the )) that closes $(( or ((.
+    fn read_arithmetic(&mut self) -> String {
+        let mut string_buffer = String::new();
+
+        let mut depth = 0;
+
+        while let Some(ch) = self.input.pop_front() {
+            match ch {
+                '(' => depth += 1,
+                ')' => {
+                    if depth == 0 && self.input.front() == Some(&')') {
+                        self.input.pop_front();
+                        break;
+                    }
+
+                    depth -= 1;
+                }
+                _ => {}
+            }
+
+            string_buffer.push(ch);
+        }
+
+        string_buffer
+    }
+
+    // the words after let up to the end of the command, joined by the comma operator.
+    fn read_let(&mut self) -> String {
+        let mut expressions = Vec::new();
+
+        let mut string_buffer = String::new();
+
+        let mut quote = None;
+
+        while let Some(ch) = self.input.pop_front() {
+            match quote {
+                Some(quote_ch) => {
+                    if ch == quote_ch {
+                        quote = None;
+                    } else {
+                        string_buffer.push(ch);
+                    }
+                }
+                None => {
+                    if matches!(ch, '\n' | ';' | '|' | '&' | ')') {
+                        self.input.push_front(ch);
+                        break;
+                    }
+
+                    match ch {
+                        '\'' | '"' => quote = Some(ch),
+                        _ if ch.is_whitespace() => {
+                            if !string_buffer.is_empty() {
+                                expressions.push(string_buffer);
+                                string_buffer = String::new();
+                            }
+                        }
+                        _ => string_buffer.push(ch),
+                    }
+                }
+            }
+        }
+
+        if !string_buffer.is_empty() {
+            expressions.push(string_buffer);
+        }
+
+        expressions.join(", ")
+    }
+
+    // the words up to ]], kept as written so that quoting can be told apart later.
+    fn read_conditional(&mut self) -> Vec<String> {
+        let mut words = Vec::new();
+
+        let mut string_buffer = String::new();
+
+        let mut quote = None;
+
+        while let Some(ch) = self.input.pop_front() {
+            match quote {
+                Some(quote_ch) => {
+                    if ch == quote_ch {
+                        quote = None;
+                    }
+                }
+                None if string_buffer == "]]"
+                    && (ch.is_whitespace() || matches!(ch, ';' | '|' | '&' | ')')) =>
+                {
+                    self.input.push_front(ch);
+                    return words;
+                }
+                None => match ch {
+                    '\'' | '"' => quote = Some(ch),
+                    // $(cmd) and ${...} are one word, spaces and all.
+                    '$' if matches!(self.input.front(), Some('(' | '{')) => {
+                        string_buffer.push(ch);
+
+                        let enclosed = match self.input.front() {
+                            Some('(') => self.read_enclosed('(', ')'),
+                            _ => self.read_enclosed('{', '}'),
+                        };
+
+                        string_buffer.push_str(&enclosed);
+
+                        continue;
+                    }
+                    '\\' => {
+                        string_buffer.push(ch);
+
+                        if let Some(ch) = self.input.pop_front() {
+                            string_buffer.push(ch);
+                        }
+
+                        continue;
+                    }
+                    _ if ch.is_whitespace() => {
+                        if !string_buffer.is_empty() {
+                            words.push(string_buffer);
+                            string_buffer = String::new();
+                        }
+
+                        continue;
+                    }
+                    _ => {}
+                },
+            }
+
+            string_buffer.push(ch);
+        }
+
+        if !string_buffer.is_empty() && string_buffer != "]]" {
+            words.push(string_buffer);
+        }
+
+        words
+    }
+
+    // from the open character up to the matching close, both included, e.g. the (cmd) of $(cmd).
+    fn read_enclosed(&mut self, open: char, close: char) -> String {
+        let mut string_buffer = String::new();
+
+        let mut depth = 0;
+
+        // a parenthesis or brace in quotes, e.g. $(echo ')'), is not counted.
+        let mut quote = None;
+
+        while let Some(ch) = self.input.pop_front() {
+            string_buffer.push(ch);
+
+            match quote {
+                Some(quote_ch) if ch == quote_ch => quote = None,
+                Some(_) => {}
+                None if ch == '\'' || ch == '"' => quote = Some(ch),
+                None if ch == open => depth += 1,
+                None if ch == close => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                None => {}
+            }
+        }
+
+        string_buffer
+    }
+
+    // the source between <( or >( and the matching ).
+    fn read_substitution(&mut self) -> String {
+        let mut string_buffer = String::new();
+
+        let mut depth = 1;
+
+        let mut quote = None;
+
+        while let Some(ch) = self.input.pop_front() {
+            match quote {
+                Some(quote_ch) => {
+                    if ch == quote_ch {
+                        quote = None;
+                    }
+                }
+                None => match ch {
+                    '\'' | '"' => quote = Some(ch),
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                },
+            }
+
+            string_buffer.push(ch);
+        }
+
+        string_buffer
+    }
+
     fn read_string(&mut self, esc: bool) -> String {
         let mut string_buffer = String::new();
 
@@ -132,11 +698,67 @@ impl Lexer {
                 if ch == '"' {
                     break;
                 }
+
+                // \" is a " of the string. any other pair is kept for the expansion, e.g. \\ before the closing ".
+                if ch == '\\' {
+                    match self.input.pop_front() {
+                        Some('"') => string_buffer.push('"'),
+                        Some(next) => {
+                            string_buffer.push(ch);
+                            string_buffer.push(next);
+                        }
+                        None => string_buffer.push(ch),
+                    }
+
+                    continue;
+                }
             } else {
-                if ch.is_whitespace() || matches!(ch, ';' | '=' | '|' | '>' | '<') {
+                // != is a word of its own, e.g. [ a != b ]
+                if ch == '=' && string_buffer == "!" {
+                    string_buffer.push(ch);
+                    continue;
+                }
+
+                if ch.is_whitespace() || matches!(ch, ';' | '=' | '|' | '&' | '>' | '<' | '(' | ')')
+                {
                     self.input.push_front(ch);
                     break;
                 }
+
+                match ch {
+                    // '...' is kept from the expansion, which takes \$ and \\ as literal.
+                    '\'' => {
+                        while let Some(ch) = self.input.pop_front() {
+                            if ch == '\'' {
+                                break;
+                            }
+
+                            if matches!(ch, '$' | '\\' | '`') {
+                                string_buffer.push('\\');
+                            }
+
+                            string_buffer.push(ch);
+                        }
+
+                        continue;
+                    }
+
+                    // $(cmd), $(( expression )) and ${...} can hold any character up to their end.
+                    '$' if matches!(self.input.front(), Some('(' | '{')) => {
+                        string_buffer.push(ch);
+
+                        let enclosed = match self.input.front() {
+                            Some('(') => self.read_enclosed('(', ')'),
+                            _ => self.read_enclosed('{', '}'),
+                        };
+
+                        string_buffer.push_str(&enclosed);
+
+                        continue;
+                    }
+
+                    _ => {}
+                }
             }
 
             string_buffer.push(ch);
@@ -145,3 +767,42 @@ impl Lexer {
         string_buffer
     }
 }
+
+// whether $key is a reference on its own: NAME, NAME[I], #NAME, !NAME, ? or {...}
+fn is_reference(key: &str) -> bool {
+    if key.starts_with('{') {
+        let mut depth = 0;
+
+        for (i, ch) in key.char_indices() {
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return i == key.len() - 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return false;
+    }
+
+    // $?, $$, $!, $#, $@ and $*
+    if matches!(key, "?" | "$" | "!" | "#" | "@" | "*") {
+        return true;
+    }
+
+    let name = key.strip_prefix(['#', '!']).unwrap_or(key);
+
+    let (name, index) = match name.split_once('[') {
+        Some((name, index)) => (name, Some(index)),
+        None => (name, None),
+    };
+
+    !name.is_empty()
+        && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+        && index.is_none_or(|index| index.ends_with(']'))
+}
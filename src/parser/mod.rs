@@ -3,40 +3,168 @@ pub mod lexer;
 pub mod token;
 use self::lexer::Lexer;
 use self::token::Token;
+use crate::alias::Alias;
 use crate::error::*;
 
-use std::iter::Peekable;
+use std::fmt;
 
 pub struct Parser {
-    lexer: Peekable<Lexer>,
+    lexer: Tokens,
+}
+
+// the tokens of the lexer with one read ahead, like Peekable, which also knows their lines.
+struct Tokens {
+    lexer: Lexer,
+    peeked: Option<(Option<Token>, usize)>,
+}
+
+impl Tokens {
+    fn next(&mut self) -> Option<Token> {
+        match self.peeked.take() {
+            Some((token, _)) => token,
+            None => self.lexer.next(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            let token = self.lexer.next();
+            self.peeked = Some((token, self.lexer.line()));
+        }
+
+        self.peeked.as_ref().and_then(|(token, _)| token.as_ref())
+    }
+
+    fn next_if(&mut self, func: impl FnOnce(&Token) -> bool) -> Option<Token> {
+        match self.peek() {
+            Some(token) if func(token) => self.next(),
+            _ => None,
+        }
+    }
+
+    fn next_if_eq(&mut self, expected: &Token) -> Option<Token> {
+        self.next_if(|token| token == expected)
+    }
+
+    // the line of the next token.
+    fn line(&mut self) -> usize {
+        self.peek();
+
+        match self.peeked {
+            Some((_, line)) => line,
+            None => self.lexer.line(),
+        }
+    }
 }
 
 impl Parser {
     pub fn new(lexer: Lexer) -> Self {
         Self {
-            lexer: lexer.peekable(),
+            lexer: Tokens {
+                lexer,
+                peeked: None,
+            },
         }
     }
 
     pub fn parse(&mut self) -> Result<Node> {
-        let mut is_pipe = false;
+        self.parse_tree(&[])
+    }
 
+    // the line the parser has reached, e.g. where a syntax error was found.
+    pub fn line(&mut self) -> usize {
+        self.lexer.line()
+    }
+
+    // the next command line, up to ; or a newline, with its pipes, && and ||. None at the end of the input.
+    // the source is run one at a time, so that an alias defined on a line can be used on the next ones.
+    pub fn parse_next(&mut self) -> Result<Option<Node>> {
+        self.parse_statement(&[])
+    }
+
+    // the lexer reads no further than the end of the last command line, so the aliases can still change.
+    pub fn set_alias(&mut self, alias: Alias) {
+        self.lexer.lexer.set_alias(alias);
+    }
+
+    // parses until the end of the input, or until one of the tokens that close a group or an item of case.
+    fn parse_tree(&mut self, end: &[Token]) -> Result<Node> {
         let mut tree = Tree::new();
 
+        while let Some(node) = self.parse_statement(end)? {
+            tree.insert(node);
+        }
+
+        Ok(Node::Tree(tree))
+    }
+
+    // a command line, or None at the end of the input or at one of the tokens of end.
+    fn parse_statement(&mut self, end: &[Token]) -> Result<Option<Node>> {
+        let mut is_pipe = false;
+
+        // the left side of && or ||, waiting for its right side.
+        let mut and_or: Option<(AndOrKind, Node)> = None;
+
         loop {
             let mut nodes = Vec::new();
+
+            let mut and_or_kind = None;
+
             loop {
+                if self.is_end(end) {
+                    break;
+                }
+
                 if let Some(node) = self.parse_insert()? {
+                    let mut inserts = vec![node];
+
+                    while let Some(node) = self.parse_insert()? {
+                        inserts.push(node);
+                    }
+
+                    // NAME=val cmd, the assignments only hold for the command.
+                    match self.parse_command()? {
+                        Some(Node::Command(mut command)) => {
+                            command.insert_assigns(inserts);
+                            nodes.push(Node::Command(command));
+                        }
+                        _ => nodes.extend(inserts),
+                    }
+
+                    continue;
+                }
+
+                if let Some(node) = self.parse_redirect()? {
                     nodes.push(node);
                     continue;
                 }
 
-                if let Some(node) = self.parse_close_fd() {
+                if let Some(node) = self.parse_group()? {
                     nodes.push(node);
                     continue;
                 }
 
-                if let Some(node) = self.parse_redirect()? {
+                if let Some(node) = self.parse_case()? {
+                    nodes.push(node);
+                    continue;
+                }
+
+                if let Some(node) = self.parse_for()? {
+                    nodes.push(node);
+                    continue;
+                }
+
+                if let Some(node) = self.parse_let() {
+                    nodes.push(node);
+                    continue;
+                }
+
+                if let Some(node) = self.parse_conditional() {
+                    nodes.push(node);
+                    continue;
+                }
+
+                if let Some(node) = self.parse_include()? {
                     nodes.push(node);
                     continue;
                 }
@@ -51,6 +179,16 @@ impl Parser {
                     continue;
                 }
 
+                if self.lexer.next_if_eq(&Token::And).is_some() {
+                    and_or_kind = Some(AndOrKind::And);
+                    break;
+                }
+
+                if self.lexer.next_if_eq(&Token::Or).is_some() {
+                    and_or_kind = Some(AndOrKind::Or);
+                    break;
+                }
+
                 if self.lexer.next_if_eq(&Token::Semicolon).is_some() {
                     break;
                 }
@@ -65,31 +203,260 @@ impl Parser {
                 }
             }
 
-            if is_pipe {
+            let node = if is_pipe {
                 let mut pipe = Pipe::new();
 
                 for node in nodes {
                     pipe.insert(node)
                 }
 
-                tree.insert(Node::Pipe(pipe));
-            } else {
+                Some(Node::Pipe(pipe))
+            } else if nodes.len() > 1 {
+                let mut nodes_tree = Tree::new();
+
                 for node in nodes {
-                    tree.insert(node)
+                    nodes_tree.insert(node)
                 }
+
+                Some(Node::Tree(nodes_tree))
+            } else {
+                nodes.pop()
+            };
+
+            let node = match (and_or.take(), node) {
+                (Some((kind, left)), Some(right)) => Some(Node::AndOr(AndOr::new(kind, left, right))),
+                (Some((kind, _)), None) => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("the right side of {kind} was not found"),
+                ))?,
+                (None, node) => node,
+            };
+
+            match (and_or_kind, node) {
+                (Some(kind), Some(node)) => and_or = Some((kind, node)),
+                (Some(kind), None) => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    format!("the left side of {kind} was not found"),
+                ))?,
+                (None, Some(node)) => return Ok(Some(node)),
+                (None, None) => {}
             }
 
-            if self.lexer.peek().is_none() {
-                break;
+            if self.lexer.peek().is_none() || self.is_end(end) {
+                if let Some((kind, _)) = and_or {
+                    Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        format!("the right side of {kind} was not found"),
+                    ))?;
+                }
+
+                return Ok(None);
             }
 
             is_pipe = false;
         }
+    }
 
-        Ok(Node::Tree(tree))
+    // ( ... ) and { ...; }, followed by the redirections of the whole group.
+    fn parse_group(&mut self) -> Result<Option<Node>> {
+        let line = self.lexer.line();
+
+        let (kind, end) = match self
+            .lexer
+            .next_if(|token| matches!(token, Token::LParen | Token::LBrace))
+        {
+            Some(Token::LParen) => (GroupKind::Subshell, Token::RParen),
+            Some(Token::LBrace) => (GroupKind::Brace, Token::RBrace),
+            _ => return Ok(None),
+        };
+
+        let node = self.parse_tree(&[end.to_owned()])?;
+
+        if self.lexer.next_if_eq(&end).is_none() {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("{end} was not found"),
+            ))?;
+        }
+
+        let mut suffix = CommandSuffix::new();
+
+        loop {
+            if let Some(node) = self.parse_redirect()? {
+                suffix.insert(node);
+                continue;
+            }
+
+            if let Some(node) = self.parse_background() {
+                suffix.insert(node);
+            }
+
+            break;
+        }
+
+        Ok(Some(Node::Group(Group::new(kind, node, suffix, line))))
+    }
+
+    fn is_end(&mut self, end: &[Token]) -> bool {
+        match self.lexer.peek() {
+            Some(token) => end.contains(token),
+            None => false,
+        }
+    }
+
+    // case WORD in PATTERN | PATTERN) ... ;; ... esac
+    fn parse_case(&mut self) -> Result<Option<Node>> {
+        let line = self.lexer.line();
+
+        if self.lexer.next_if_eq(&Token::Case).is_none() {
+            return Ok(None);
+        }
+
+        let word = match self.parse_reference().or_else(|| self.parse_string()) {
+            Some(word) => word,
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "the word of case was not found".to_owned(),
+            ))?,
+        };
+
+        if self
+            .lexer
+            .next_if_eq(&Token::String("in".to_owned()))
+            .is_none()
+        {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "in was not found after the word of case".to_owned(),
+            ))?;
+        }
+
+        let mut case = Case::new(word, line);
+
+        loop {
+            while self.lexer.next_if_eq(&Token::Semicolon).is_some() {}
+
+            if self.lexer.next_if_eq(&Token::Esac).is_some() {
+                break;
+            }
+
+            if self.lexer.peek().is_none() {
+                Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    "esac was not found".to_owned(),
+                ))?;
+            }
+
+            // the ( before the patterns is optional.
+            self.lexer.next_if_eq(&Token::LParen);
+
+            let mut patterns = Vec::new();
+
+            loop {
+                match self.parse_reference().or_else(|| self.parse_string()) {
+                    Some(pattern) => patterns.push(pattern),
+                    None => Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        "a pattern of case was not found".to_owned(),
+                    ))?,
+                }
+
+                if self.lexer.next_if_eq(&Token::Pipe).is_some() {
+                    continue;
+                }
+
+                if self.lexer.next_if_eq(&Token::RParen).is_some() {
+                    break;
+                }
+
+                Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    ") was not found after the patterns of case".to_owned(),
+                ))?;
+            }
+
+            let node = self.parse_tree(&[
+                Token::SemicolonSemicolon,
+                Token::SemicolonAnd,
+                Token::SemicolonSemicolonAnd,
+                Token::Esac,
+            ])?;
+
+            // the last item can be closed by esac alone.
+            let terminator = match self.lexer.next_if(|token| {
+                matches!(
+                    token,
+                    Token::SemicolonSemicolon | Token::SemicolonAnd | Token::SemicolonSemicolonAnd
+                )
+            }) {
+                Some(Token::SemicolonAnd) => CaseTerminator::FallThrough,
+                Some(Token::SemicolonSemicolonAnd) => CaseTerminator::Continue,
+                _ => CaseTerminator::Break,
+            };
+
+            case.insert(CaseItem::new(patterns, node, terminator));
+        }
+
+        Ok(Some(Node::Case(case)))
+    }
+
+    // for NAME in WORD ...; do ...; done
+    fn parse_for(&mut self) -> Result<Option<Node>> {
+        let line = self.lexer.line();
+
+        if self.lexer.next_if_eq(&Token::For).is_none() {
+            return Ok(None);
+        }
+
+        let name = match self.parse_string() {
+            Some(Node::String(name)) => name,
+            _ => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "the name of for was not found".to_owned(),
+            ))?,
+        };
+
+        if self
+            .lexer
+            .next_if_eq(&Token::String("in".to_owned()))
+            .is_none()
+        {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "in was not found after the name of for".to_owned(),
+            ))?;
+        }
+
+        let mut words = Vec::new();
+
+        while let Some(node) = self.parse_word() {
+            words.push(node);
+        }
+
+        while self.lexer.next_if_eq(&Token::Semicolon).is_some() {}
+
+        if self.lexer.next_if_eq(&Token::Do).is_none() {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "do was not found after the words of for".to_owned(),
+            ))?;
+        }
+
+        let node = self.parse_tree(&[Token::Done])?;
+
+        if self.lexer.next_if_eq(&Token::Done).is_none() {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "done was not found".to_owned(),
+            ))?;
+        }
+
+        Ok(Some(Node::For(For::new(name, words, node, line))))
     }
 
     fn parse_command(&mut self) -> Result<Option<Node>> {
+        let line = self.lexer.line();
+
         let prefix = match self.parse_reference().or_else(|| self.parse_string()) {
             Some(prefix) => prefix,
             None => return Ok(None),
@@ -97,7 +464,7 @@ impl Parser {
 
         let suffix = self.parse_command_suffix()?;
 
-        let mut command = Command::new();
+        let mut command = Command::new(line);
         command.insert_prefix(prefix);
         command.insert_suffix(suffix);
 
@@ -108,35 +475,39 @@ impl Parser {
         let mut suffix = CommandSuffix::new();
 
         loop {
-            match self.lexer.peek() {
-                Some(peek_token) => {
-                    if matches!(peek_token, Token::Pipe | Token::Semicolon) {
-                        break;
-                    }
-                }
-                None => break,
-            }
-
             if self.lexer.next_if_eq(&Token::Ampersand).is_some() {
                 suffix.insert(Node::Background(true));
                 break;
             }
 
-            if let Some(node) = self.parse_reference().or(self.parse_string()) {
+            if let Some(node) = self.parse_reference().or_else(|| self.parse_string()) {
                 suffix.insert(node);
+                continue;
             }
 
-            if let Some(node) = self.parse_string().or_else(|| self.parse_reference()) {
+            // NAME=val as an argument, e.g. export NAME=val
+            if let Some(node) = self.parse_insert()? {
                 suffix.insert(node);
+                continue;
             }
 
-            if let Some(node) = self.parse_redirect()? {
+            if let Some(node) = self.parse_process_substitution()? {
                 suffix.insert(node);
+                continue;
             }
 
-            if let Some(node) = self.parse_background() {
+            if let Some(node) = self.parse_arithmetic() {
+                suffix.insert(node);
+                continue;
+            }
+
+            if let Some(node) = self.parse_redirect()? {
                 suffix.insert(node);
+                continue;
             }
+
+            // anything else ends the command, e.g. | ; && || )
+            break;
         }
 
         Ok(suffix)
@@ -145,8 +516,8 @@ impl Parser {
     fn parse_redirect(&mut self) -> Result<Option<Node>> {
         let left = match self.parse_fd().or_else(|| match self.lexer.peek() {
             Some(peek_token) => match peek_token {
-                Token::Lt => Some(Node::FD(0)),
-                Token::Gt => Some(Node::FD(1)),
+                Token::Lt | Token::LtLtLt | Token::HereDoc(..) => Some(Node::FD(0)),
+                Token::Gt | Token::GtGt => Some(Node::FD(1)),
                 _ => None,
             },
             None => None,
@@ -155,10 +526,31 @@ impl Parser {
             None => return Ok(None),
         };
 
+        let left_fd = match left {
+            Node::FD(fd) => fd,
+            _ => return Ok(None),
+        };
+
+        if left_fd > 9 {
+            Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("file descriptor out of range: {left_fd}"),
+            ))?;
+        }
+
         let kind = match self.lexer.next() {
             Some(token) => match token {
                 Token::Lt => RedirectKind::Input,
                 Token::Gt => RedirectKind::Output,
+                Token::GtGt => RedirectKind::Append,
+                Token::LtLtLt => RedirectKind::HereString,
+                Token::HereDoc(body, is_expand) => {
+                    let mut redirect = Redirect::new(RedirectKind::HereDoc);
+                    redirect.insert_left(left);
+                    redirect.insert_right(Node::HereDoc(HereDoc::new(body, is_expand)));
+
+                    return Ok(Some(Node::Redirect(redirect)));
+                }
                 _ => Err(Error::new(
                     ErrorKind::WrongSyntax,
                     format!("{} token cannot be used", token),
@@ -171,7 +563,36 @@ impl Parser {
             ))?,
         };
 
-        let right = match self.parse_fd().or_else(|| self.parse_string()) {
+        if matches!(kind, RedirectKind::HereString) {
+            let right = match self.parse_string().or_else(|| self.parse_reference()) {
+                Some(right) => right,
+                None => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    "no here-string specified".to_owned(),
+                ))?,
+            };
+
+            let mut redirect = Redirect::new(kind);
+            redirect.insert_left(left);
+            redirect.insert_right(right);
+
+            return Ok(Some(Node::Redirect(redirect)));
+        }
+
+        if let Some(right) = self.parse_process_substitution()? {
+            let mut redirect = Redirect::new(kind);
+            redirect.insert_left(left);
+            redirect.insert_right(right);
+
+            return Ok(Some(Node::Redirect(redirect)));
+        }
+
+        let right = match self
+            .parse_fd()
+            .or_else(|| self.parse_close_fd(left_fd))
+            .or_else(|| self.parse_string())
+            .or_else(|| self.parse_reference())
+        {
             Some(right) => right,
             None => Err(Error::new(
                 ErrorKind::WrongSyntax,
@@ -186,24 +607,83 @@ impl Parser {
         Ok(Some(Node::Redirect(redirect)))
     }
 
-    fn parse_close_fd(&mut self) -> Option<Node> {
-        match self.lexer.next_if(|token| match token {
-            Token::FD(fd) => fd < &0,
-            _ => false,
+    // the right side of N>&- or N<&-, closes N.
+    fn parse_close_fd(&mut self, fd: u32) -> Option<Node> {
+        self.lexer
+            .next_if(|token| match token {
+                Token::FD(fd) => fd < &0,
+                _ => false,
+            })
+            .map(|_| Node::CloseFD(fd))
+    }
+
+    fn parse_process_substitution(&mut self) -> Result<Option<Node>> {
+        let (kind, source) = match self.lexer.next_if(|token| {
+            matches!(
+                token,
+                Token::InputSubstitution(_) | Token::OutputSubstitution(_)
+            )
         }) {
-            Some(token) => match token {
-                Token::FD(fd) => Some(Node::CloseFD(
-                    i32::to_string(&fd)
-                        .pop()
-                        .unwrap()
-                        .to_string()
-                        .parse::<u32>()
-                        .unwrap(),
-                )),
-                _ => None,
-            },
+            Some(Token::InputSubstitution(source)) => (SubstitutionKind::Input, source),
+            Some(Token::OutputSubstitution(source)) => (SubstitutionKind::Output, source),
+            _ => return Ok(None),
+        };
 
-            None => None,
+        let mut lexer = Lexer::new(source.chars().collect());
+        lexer.set_alias(self.lexer.lexer.alias().to_owned());
+
+        let node = Parser::new(lexer).parse()?;
+
+        Ok(Some(Node::ProcessSubstitution(ProcessSubstitution::new(
+            kind, node,
+        ))))
+    }
+
+    fn parse_let(&mut self) -> Option<Node> {
+        let line = self.lexer.line();
+
+        match self.lexer.next_if(|token| matches!(token, Token::Let(_))) {
+            Some(Token::Let(string)) => Some(Node::Let(string, line)),
+            _ => None,
+        }
+    }
+
+    fn parse_conditional(&mut self) -> Option<Node> {
+        let line = self.lexer.line();
+
+        match self
+            .lexer
+            .next_if(|token| matches!(token, Token::Conditional(_)))
+        {
+            Some(Token::Conditional(words)) => Some(Node::Conditional(words, line)),
+            _ => None,
+        }
+    }
+
+    // include FILE
+    fn parse_include(&mut self) -> Result<Option<Node>> {
+        let line = self.lexer.line();
+
+        if self.lexer.next_if_eq(&Token::Include).is_none() {
+            return Ok(None);
+        }
+
+        match self.parse_reference().or_else(|| self.parse_string()) {
+            Some(node) => Ok(Some(Node::Include(Box::new(node), line))),
+            None => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "include: a file is expected".to_owned(),
+            )),
+        }
+    }
+
+    fn parse_arithmetic(&mut self) -> Option<Node> {
+        match self
+            .lexer
+            .next_if(|token| matches!(token, Token::Arithmetic(_)))
+        {
+            Some(Token::Arithmetic(string)) => Some(Node::Arithmetic(string)),
+            _ => None,
         }
     }
 
@@ -219,12 +699,98 @@ impl Parser {
             Token::FD(fd) => fd >= &0,
             _ => false,
         }) {
-            Some(token) => match token {
-                Token::FD(fd) => Some(Node::FD(fd.try_into().unwrap())),
-                _ => None,
-            },
+            Some(Token::FD(fd)) => Some(Node::FD(fd.try_into().unwrap())),
+            _ => None,
+        }
+    }
 
-            None => None,
+    // a word that expands to strings, e.g. an element of a list or of for.
+    fn parse_word(&mut self) -> Option<Node> {
+        self.parse_reference()
+            .or_else(|| self.parse_string())
+            .or_else(|| self.parse_arithmetic())
+    }
+
+    // the ( ... ) of =xs (a b c), =t (a, b, c) and =m ([key]=val ...), after the (.
+    fn parse_list(&mut self) -> Result<Node> {
+        let mut items = Vec::new();
+
+        let mut entries = Vec::new();
+
+        // a comma after an element makes a tuple.
+        let mut is_tuple = false;
+
+        loop {
+            if self.lexer.next_if_eq(&Token::RParen).is_some() {
+                break;
+            }
+
+            // the list can go on over several lines.
+            if self.lexer.next_if_eq(&Token::Semicolon).is_some() {
+                continue;
+            }
+
+            // [key]=val, where the lexer puts the = in front.
+            if self.lexer.next_if_eq(&Token::Equal).is_some() {
+                let key = match self.parse_string() {
+                    Some(Node::String(key)) if key.starts_with('[') && key.ends_with(']') => {
+                        key[1..key.len() - 1].to_owned()
+                    }
+                    _ => Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        "[key]=val was expected in the map".to_owned(),
+                    ))?,
+                };
+
+                let val = self
+                    .parse_reference()
+                    .or_else(|| self.parse_string())
+                    .unwrap_or(Node::String(String::new()));
+
+                entries.push((key, val));
+
+                continue;
+            }
+
+            match self.parse_word() {
+                Some(Node::String(mut string)) => {
+                    if string.ends_with(',') {
+                        is_tuple = true;
+                        string.pop();
+
+                        if string.is_empty() {
+                            continue;
+                        }
+                    }
+
+                    items.push(Node::String(string))
+                }
+                Some(Node::Reference(mut key)) => {
+                    if key.ends_with(',') {
+                        is_tuple = true;
+                        key.pop();
+                    }
+
+                    items.push(Node::Reference(key))
+                }
+                Some(node) => items.push(node),
+                None => Err(Error::new(
+                    ErrorKind::WrongSyntax,
+                    ") was not found at the end of the list".to_owned(),
+                ))?,
+            }
+        }
+
+        match (items.is_empty(), entries.is_empty()) {
+            (true, false) => Ok(Node::Map(entries)),
+            (false, false) => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                "elements and [key]=val cannot be mixed in a list".to_owned(),
+            )),
+            _ => match is_tuple {
+                true => Ok(Node::Tuple(items)),
+                false => Ok(Node::Array(items)),
+            },
         }
     }
 
@@ -233,11 +799,8 @@ impl Parser {
             .lexer
             .next_if(|token| matches!(token, Token::String(_)))
         {
-            Some(token) => match token {
-                Token::String(string) => Some(Node::String(string)),
-                _ => None,
-            },
-            None => None,
+            Some(Token::String(string)) => Some(Node::String(string)),
+            _ => None,
         }
     }
 
@@ -246,11 +809,8 @@ impl Parser {
             .lexer
             .next_if(|token| matches!(token, Token::Variable(_)))
         {
-            Some(token) => match token {
-                Token::Variable(string) => Some(Node::Reference(string)),
-                _ => None,
-            },
-            None => None,
+            Some(Token::Variable(string)) => Some(Node::Reference(string)),
+            _ => None,
         }
     }
 
@@ -259,6 +819,8 @@ impl Parser {
         //     return Ok(None);
         // }
 
+        let line = self.lexer.line();
+
         if self.lexer.next_if_eq(&Token::Equal).is_none() {
             return Ok(None);
         }
@@ -271,7 +833,16 @@ impl Parser {
             ))?,
         };
 
-        let right = match self.parse_string() {
+        if self.lexer.next_if_eq(&Token::LParen).is_some() {
+            let mut insert = Insert::new(line);
+            insert.insert_key(left);
+            insert.insert_val(self.parse_list()?);
+
+            return Ok(Some(Node::Insert(insert)));
+        }
+
+        // any word, e.g. =B $A, =P $HOME/bin, =S "$A and $B", =D $(date) or =N $((N + 1))
+        let right = match self.parse_word() {
             Some(node) => node,
             None => Err(Error::new(
                 ErrorKind::WrongSyntax,
@@ -279,7 +850,7 @@ impl Parser {
             ))?,
         };
 
-        let mut insert = Insert::new();
+        let mut insert = Insert::new(line);
         insert.insert_key(left);
         insert.insert_val(right);
 
@@ -297,6 +868,26 @@ pub enum Node {
     Insert(Insert),
     Redirect(Redirect),
     Background(bool),
+    HereDoc(HereDoc),
+    ProcessSubstitution(ProcessSubstitution),
+    Group(Group),
+    Case(Case),
+    AndOr(AndOr),
+    // (( expression )), let expression, and its line
+    Let(String, usize),
+    // $(( expression ))
+    Arithmetic(String),
+    // [[ expression ]], the words as written, and its line
+    Conditional(Vec<String>, usize),
+    // include FILE, and its line
+    Include(Box<Node>, usize),
+    // (a b c)
+    Array(Vec<Node>),
+    // (a, b, c)
+    Tuple(Vec<Node>),
+    // ([key]=val ...)
+    Map(Vec<(String, Node)>),
+    For(For),
     Tree(Tree),
     Pipe(Pipe),
 }
@@ -305,16 +896,22 @@ pub enum Node {
 pub struct Insert {
     key: Option<Box<Node>>,
     val: Option<Box<Node>>,
+    line: usize,
 }
 
 impl Insert {
-    fn new() -> Self {
+    fn new(line: usize) -> Self {
         Self {
             key: None,
             val: None,
+            line,
         }
     }
 
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
     fn insert_key(&mut self, key: Node) {
         self.key = Some(Box::new(key))
     }
@@ -337,17 +934,11 @@ impl Insert {
     // }
 
     pub fn take_key(&mut self) -> Option<Node> {
-        match self.key.take() {
-            Some(key) => Some(*key),
-            None => None,
-        }
+        self.key.take().map(|key| *key)
     }
 
     pub fn take_val(&mut self) -> Option<Node> {
-        match self.val.take() {
-            Some(val) => Some(*val),
-            None => None,
-        }
+        self.val.take().map(|val| *val)
     }
 }
 
@@ -355,6 +946,30 @@ impl Insert {
 pub enum RedirectKind {
     Input,
     Output,
+    Append,
+    HereDoc,
+    HereString,
+}
+
+#[derive(Debug, Clone)]
+pub struct HereDoc {
+    body: String,
+    is_expand: bool,
+}
+
+impl HereDoc {
+    fn new(body: String, is_expand: bool) -> Self {
+        Self { body, is_expand }
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    // false when the delimiter was quoted, e.g. <<'EOF'
+    pub fn is_expand(&self) -> bool {
+        self.is_expand
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -367,7 +982,7 @@ pub struct Redirect {
 impl Redirect {
     fn new(kind: RedirectKind) -> Self {
         Self {
-            kind: kind,
+            kind,
             left: None,
             right: None,
         }
@@ -393,32 +1008,273 @@ impl Redirect {
         self.right = Some(Box::new(node))
     }
 }
+#[derive(Debug, Clone)]
+pub enum SubstitutionKind {
+    // <(cmd), read what cmd writes
+    Input,
+    // >(cmd), write to what cmd reads
+    Output,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessSubstitution {
+    kind: SubstitutionKind,
+    node: Option<Box<Node>>,
+}
+
+impl ProcessSubstitution {
+    fn new(kind: SubstitutionKind, node: Node) -> Self {
+        Self {
+            kind,
+            node: Some(Box::new(node)),
+        }
+    }
+
+    pub fn kind(&self) -> &SubstitutionKind {
+        &self.kind
+    }
+
+    pub fn take_node(&mut self) -> Option<Node> {
+        self.node.take().map(|node| *node)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum GroupKind {
+    // ( ... ), runs in a forked shell
+    Subshell,
+    // { ...; }, runs in the current shell
+    Brace,
+}
+
+#[derive(Debug, Clone)]
+pub struct Group {
+    kind: GroupKind,
+    node: Option<Box<Node>>,
+    suffix: Option<Box<CommandSuffix>>,
+    line: usize,
+}
+
+impl Group {
+    fn new(kind: GroupKind, node: Node, suffix: CommandSuffix, line: usize) -> Self {
+        Self {
+            kind,
+            node: Some(Box::new(node)),
+            suffix: Some(Box::new(suffix)),
+            line,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn kind(&self) -> &GroupKind {
+        &self.kind
+    }
+
+    pub fn take_node(&mut self) -> Option<Node> {
+        self.node.take().map(|node| *node)
+    }
+
+    // the redirections and & of the whole group.
+    pub fn take_suffix(&mut self) -> Option<CommandSuffix> {
+        self.suffix.take().map(|suffix| *suffix)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CaseTerminator {
+    // ;;, ends the case
+    Break,
+    // ;&, runs the next item without matching its patterns
+    FallThrough,
+    // ;;&, goes on matching the patterns of the next items
+    Continue,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseItem {
+    patterns: Vec<Node>,
+    node: Option<Box<Node>>,
+    terminator: CaseTerminator,
+}
+
+impl CaseItem {
+    fn new(patterns: Vec<Node>, node: Node, terminator: CaseTerminator) -> Self {
+        Self {
+            patterns,
+            node: Some(Box::new(node)),
+            terminator,
+        }
+    }
+
+    pub fn patterns(&self) -> &[Node] {
+        &self.patterns
+    }
+
+    pub fn terminator(&self) -> &CaseTerminator {
+        &self.terminator
+    }
+
+    pub fn take_node(&mut self) -> Option<Node> {
+        self.node.take().map(|node| *node)
+    }
+}
+
+// for name in words; do ...; done
+#[derive(Debug, Clone)]
+pub struct For {
+    name: String,
+    words: Vec<Node>,
+    node: Option<Box<Node>>,
+    line: usize,
+}
+
+impl For {
+    fn new(name: String, words: Vec<Node>, node: Node, line: usize) -> Self {
+        Self {
+            name,
+            words,
+            node: Some(Box::new(node)),
+            line,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn take_words(&mut self) -> Vec<Node> {
+        std::mem::take(&mut self.words)
+    }
+
+    pub fn take_node(&mut self) -> Option<Node> {
+        self.node.take().map(|node| *node)
+    }
+}
+
+// case word in pattern) ... ;; esac
+#[derive(Debug, Clone)]
+pub struct Case {
+    word: Option<Box<Node>>,
+    items: Vec<CaseItem>,
+    line: usize,
+}
+
+impl Case {
+    fn new(word: Node, line: usize) -> Self {
+        Self {
+            word: Some(Box::new(word)),
+            items: Vec::new(),
+            line,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    fn insert(&mut self, item: CaseItem) {
+        self.items.push(item)
+    }
+
+    pub fn take_word(&mut self) -> Option<Node> {
+        self.word.take().map(|node| *node)
+    }
+
+    pub fn take_items(&mut self) -> Vec<CaseItem> {
+        std::mem::take(&mut self.items)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AndOrKind {
+    And,
+    Or,
+}
+
+impl fmt::Display for AndOrKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AndOrKind::And => write!(f, "&&"),
+            AndOrKind::Or => write!(f, "||"),
+        }
+    }
+}
+
+// left && right, left || right
+#[derive(Debug, Clone)]
+pub struct AndOr {
+    kind: AndOrKind,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+impl AndOr {
+    fn new(kind: AndOrKind, left: Node, right: Node) -> Self {
+        Self {
+            kind,
+            left: Some(Box::new(left)),
+            right: Some(Box::new(right)),
+        }
+    }
+
+    pub fn kind(&self) -> &AndOrKind {
+        &self.kind
+    }
+
+    pub fn take_left(&mut self) -> Option<Node> {
+        self.left.take().map(|node| *node)
+    }
+
+    pub fn take_right(&mut self) -> Option<Node> {
+        self.right.take().map(|node| *node)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Command {
+    // NAME=val before the command
+    assigns: Vec<Node>,
     prefix: Option<Box<Node>>,
     suffix: Option<Box<CommandSuffix>>,
+    // where the command starts in the source, $LINENO
+    line: usize,
 }
 
 impl Command {
-    fn new() -> Self {
+    fn new(line: usize) -> Self {
         Self {
+            assigns: Vec::new(),
             prefix: None,
             suffix: None,
+            line,
         }
     }
 
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn take_assigns(&mut self) -> Vec<Node> {
+        std::mem::take(&mut self.assigns)
+    }
+
+    fn insert_assigns(&mut self, assigns: Vec<Node>) {
+        self.assigns = assigns
+    }
+
     pub fn take_prefix(&mut self) -> Option<Node> {
-        match self.prefix.to_owned() {
-            Some(node) => Some(*node),
-            None => None,
-        }
+        self.prefix.to_owned().map(|node| *node)
     }
 
     pub fn take_suffix(&mut self) -> Option<CommandSuffix> {
-        match self.suffix.take() {
-            Some(suffix) => Some(*suffix),
-            None => None,
-        }
+        self.suffix.take().map(|suffix| *suffix)
     }
 
     fn insert_prefix(&mut self, prefix: Node) {
@@ -435,9 +1291,7 @@ pub struct CommandSuffix(StraightBTree);
 
 impl CommandSuffix {
     fn new() -> Self {
-        Self {
-            0: StraightBTree::new(),
-        }
+        Self(StraightBTree::new())
     }
 
     fn insert(&mut self, node: Node) {
@@ -454,9 +1308,7 @@ pub struct Pipe(StraightBTree);
 
 impl Pipe {
     fn new() -> Self {
-        Self {
-            0: StraightBTree::new(),
-        }
+        Self(StraightBTree::new())
     }
 
     fn insert(&mut self, node: Node) {
@@ -537,7 +1389,7 @@ impl StraightBTree {
         }
 
         if let Some(node) = self.child.as_ref() {
-            if node.node.is_some() == false {
+            if node.node.is_none() {
                 return node.is_child();
             } else {
                 return true;
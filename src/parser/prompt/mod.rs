@@ -1,3 +1,4 @@
+use crate::manifest;
 use std::env;
 
 //DaMeDaYo->pub fn parse(source: String) -> String {
@@ -5,12 +6,13 @@ pub fn parse(source: &str) -> String {
     source
         .replace("\\W", &get_current_dir_path(true))
         .replace("\\w", &get_current_dir_path(false))
+        .replace("\\v", manifest::version())
 }
 
 fn get_current_dir_path(is_full_path: bool) -> String {
     match env::current_dir() {
         Ok(path) => {
-            if is_full_path == false {
+            if !is_full_path {
                 path.file_name()
                     .unwrap_or_default()
                     .to_string_lossy()
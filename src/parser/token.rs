@@ -9,12 +9,33 @@ pub enum Token {
     Variable(String), //$A
     Equal,            // =
     Ampersand,        // &
-    // Let,              // let a = b
+    Let(String),       // let a=b, (( a = b ))
+    Arithmetic(String), // $(( a + b ))
+    Conditional(Vec<String>), // [[ a =~ b ]], the words as written
     Gt,        // >
+    GtGt,      // >>
     Lt,        // <
-    // Include,   //include
+    LtLtLt,    // <<<
+    HereDoc(String, bool), // <<EOF, the body and whether it is expanded
+    InputSubstitution(String),  // <(cmd)
+    OutputSubstitution(String), // >(cmd)
+    Include,   // include
     Pipe,      // |
     Semicolon, // ;
+    SemicolonSemicolon,          // ;;
+    SemicolonAnd,                // ;&
+    SemicolonSemicolonAnd,       // ;;&
+    And,       // &&
+    Or,        // ||
+    LParen,    // (
+    RParen,    // )
+    LBrace,    // {
+    RBrace,    // }
+    Case,      // case
+    Esac,      // esac
+    For,       // for
+    Do,        // do
+    Done,      // done
 }
 
 impl Display for Token {
@@ -25,12 +46,33 @@ impl Display for Token {
             Token::Variable(string) => write!(tkn, "{string}"),
             Token::Equal => write!(tkn, "="),
             Token::Ampersand => write!(tkn, "&"),
-            // Token::Let => write!(tkn, "let"),
+            Token::Let(string) => write!(tkn, "(({string}))"),
+            Token::Arithmetic(string) => write!(tkn, "$(({string}))"),
+            Token::Conditional(words) => write!(tkn, "[[ {} ]]", words.join(" ")),
             Token::Gt => write!(tkn, ">"),
+            Token::GtGt => write!(tkn, ">>"),
             Token::Lt => write!(tkn, "<"),
-            // Token::Include => write!(tkn, "include"),
+            Token::LtLtLt => write!(tkn, "<<<"),
+            Token::HereDoc(..) => write!(tkn, "<<"),
+            Token::InputSubstitution(string) => write!(tkn, "<({string})"),
+            Token::OutputSubstitution(string) => write!(tkn, ">({string})"),
+            Token::Include => write!(tkn, "include"),
             Token::Pipe => write!(tkn, "|"),
             Token::Semicolon => write!(tkn, ";"),
+            Token::SemicolonSemicolon => write!(tkn, ";;"),
+            Token::SemicolonAnd => write!(tkn, ";&"),
+            Token::SemicolonSemicolonAnd => write!(tkn, ";;&"),
+            Token::And => write!(tkn, "&&"),
+            Token::Or => write!(tkn, "||"),
+            Token::LParen => write!(tkn, "("),
+            Token::RParen => write!(tkn, ")"),
+            Token::LBrace => write!(tkn, "{{"),
+            Token::RBrace => write!(tkn, "}}"),
+            Token::Case => write!(tkn, "case"),
+            Token::Esac => write!(tkn, "esac"),
+            Token::For => write!(tkn, "for"),
+            Token::Do => write!(tkn, "do"),
+            Token::Done => write!(tkn, "done"),
         }
     }
 }
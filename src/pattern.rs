@@ -0,0 +1,79 @@
+use crate::error::*;
+use regex::Regex;
+
+fn compile(pattern: &str) -> Result<Regex> {
+    match Regex::new(pattern) {
+        Ok(regex) => Ok(regex),
+        Err(err) => Err(Error::new(
+            ErrorKind::WrongSyntax,
+            format!("invalid regex: {err}"),
+        )),
+    }
+}
+
+// the whole match followed by every capture group, for [[ text =~ pattern ]].
+// a group that did not take part in the match is an empty string.
+pub fn captures(pattern: &str, text: &str) -> Result<Option<Vec<String>>> {
+    let captures = match compile(pattern)?.captures(text) {
+        Some(captures) => captures,
+        None => return Ok(None),
+    };
+
+    Ok(Some(
+        captures
+            .iter()
+            .map(|capture| match capture {
+                Some(capture) => capture.as_str().to_owned(),
+                None => String::new(),
+            })
+            .collect(),
+    ))
+}
+
+// ${NAME/pattern/replacement} replaces the first match, ${NAME//pattern/replacement} every match.
+// the replacement can refer to the groups as $1 or ${name}.
+pub fn replace(text: &str, pattern: &str, replacement: &str, is_all: bool) -> Result<String> {
+    let regex = compile(pattern)?;
+
+    match is_all {
+        true => Ok(regex.replace_all(text, replacement).into_owned()),
+        false => Ok(regex.replace(text, replacement).into_owned()),
+    }
+}
+
+// splits the inside of ${NAME/pattern/replacement} into (NAME, pattern, replacement, is_all),
+// or returns None when it is a plain ${NAME}. a slash in the pattern is written as \/.
+pub fn parse_substitution(source: &str) -> Option<(&str, String, &str, bool)> {
+    let (name, rest) = source.split_once('/')?;
+
+    let (rest, is_all) = match rest.strip_prefix('/') {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+
+    let mut pattern = String::new();
+
+    let mut chars = rest.char_indices();
+
+    let mut replacement = "";
+
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some((_, '/')) => pattern.push('/'),
+                Some((_, ch)) => {
+                    pattern.push('\\');
+                    pattern.push(ch);
+                }
+                None => pattern.push('\\'),
+            },
+            '/' => {
+                replacement = &rest[i + 1..];
+                break;
+            }
+            _ => pattern.push(ch),
+        }
+    }
+
+    Some((name, pattern, replacement, is_all))
+}
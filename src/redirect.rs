@@ -0,0 +1,257 @@
+use crate::error::*;
+use crate::parser::RedirectKind;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Seek;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::FromRawFd;
+use std::os::unix::io::RawFd;
+use std::process;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+// files opened for a redirect are moved to this fd or above,
+// so that they never collide with the 0 ~ 9 that a command can address.
+const HIGH_FD: RawFd = 10;
+
+// numbers the temporary files of here-documents within this process.
+static HERE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+enum Action {
+    Open(File),
+    Dup(RawFd),
+    Close,
+}
+
+// the redirections of one command, in the order they were written.
+// applied in the child between fork and exec, or to the shell itself by exec.
+pub struct RedirectTable(Vec<(RawFd, Action)>);
+
+impl Default for RedirectTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RedirectTable {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // N>file, N>>file, N<file
+    pub fn open(&mut self, fd: RawFd, path: &str, kind: &RedirectKind) -> Result<()> {
+        let file = match kind {
+            RedirectKind::Input | RedirectKind::HereDoc | RedirectKind::HereString => {
+                File::options().read(true).open(path)
+            }
+            RedirectKind::Output => File::options()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(path),
+            RedirectKind::Append => File::options().create(true).append(true).open(path),
+        };
+
+        match file {
+            Ok(file) => {
+                self.0.push((fd, Action::Open(high(file)?)));
+                Ok(())
+            }
+            Err(err) => Err(Error::new(ErrorKind::OpenFailed, format!("{path}: {err}"))),
+        }
+    }
+
+    // N<<EOF, N<<<word
+    // the text reaches the command through a temporary file that is unlinked right away.
+    pub fn here(&mut self, fd: RawFd, text: &str) -> Result<()> {
+        let path = env::temp_dir().join(format!(
+            "nsh-{}-{}",
+            process::id(),
+            HERE_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        // only the shell can read the body, which may hold secrets, until it is unlinked.
+        let mut file = match File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(err) => Err(Error::new(ErrorKind::CreateFailed, err.to_string()))?,
+        };
+
+        fs::remove_file(&path).ok();
+
+        if let Err(err) = file.write_all(text.as_bytes()).and_then(|_| file.rewind()) {
+            Err(Error::new(ErrorKind::CreateFailed, err.to_string()))?
+        }
+
+        self.0.push((fd, Action::Open(high(file)?)));
+
+        Ok(())
+    }
+
+    // N<file, where the file is already open, e.g. < <(cmd)
+    pub fn file(&mut self, fd: RawFd, file: File) {
+        self.0.push((fd, Action::Open(file)))
+    }
+
+    // keeps the file open in the command under its own number, e.g. for /dev/fd/N.
+    pub fn pass(&mut self, file: File) -> RawFd {
+        let fd = file.as_raw_fd();
+
+        self.0.push((fd, Action::Open(file)));
+
+        fd
+    }
+
+    // N>&M, N<&M
+    pub fn dup(&mut self, fd: RawFd, src: RawFd) {
+        self.0.push((fd, Action::Dup(src)))
+    }
+
+    // N>&-, N<&-
+    pub fn close(&mut self, fd: RawFd) {
+        self.0.push((fd, Action::Close))
+    }
+
+    // applies the table to the shell itself for the time being, e.g. { ...; } > file
+    pub fn apply_shell(&self) -> Result<Saved> {
+        io::stdout().flush().ok();
+
+        let mut saved = Saved(Vec::new());
+
+        for (fd, _) in self.0.iter() {
+            if saved.0.iter().any(|(saved_fd, _)| saved_fd == fd) {
+                continue;
+            }
+
+            // an fd that was not open is closed again on restore.
+            let copy = unsafe { libc::fcntl(*fd, libc::F_DUPFD_CLOEXEC, HIGH_FD) };
+
+            saved.0.push((
+                *fd,
+                match copy < 0 {
+                    true => None,
+                    false => Some(unsafe { File::from_raw_fd(copy) }),
+                },
+            ));
+        }
+
+        if let Err(err) = self.apply() {
+            saved.restore();
+
+            return Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()));
+        }
+
+        Ok(saved)
+    }
+
+    // only calls async-signal-safe functions, so it can be used in pre_exec.
+    pub fn apply(&self) -> io::Result<()> {
+        for (fd, action) in self.0.iter() {
+            match action {
+                Action::Open(file) => dup_to(file.as_raw_fd(), *fd)?,
+                Action::Dup(src) => dup_to(*src, *fd)?,
+                Action::Close => unsafe {
+                    libc::close(*fd);
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// the fds of the shell that a redirect table replaced.
+pub struct Saved(Vec<(RawFd, Option<File>)>);
+
+impl Saved {
+    pub fn restore(self) {
+        io::stdout().flush().ok();
+
+        for (fd, file) in self.0.into_iter().rev() {
+            match file {
+                Some(file) => unsafe {
+                    libc::dup2(file.as_raw_fd(), fd);
+                },
+                None => unsafe {
+                    libc::close(fd);
+                },
+            }
+        }
+    }
+}
+
+fn dup_to(src: RawFd, fd: RawFd) -> io::Result<()> {
+    if src == fd {
+        // dup2 does nothing here, so the close-on-exec flag has to be dropped by hand.
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+
+        if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(());
+    }
+
+    if unsafe { libc::dup2(src, fd) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+fn high(file: File) -> Result<File> {
+    let fd = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_DUPFD_CLOEXEC, HIGH_FD) };
+
+    if fd < 0 {
+        return Err(Error::new(
+            ErrorKind::OpenFailed,
+            io::Error::last_os_error().to_string(),
+        ));
+    }
+
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+// (read, write), both ends are closed on exec until a table passes them on.
+pub fn pipe() -> Result<(File, File)> {
+    let mut fds: [RawFd; 2] = [0; 2];
+
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        return Err(Error::new(
+            ErrorKind::CreationFailed,
+            io::Error::last_os_error().to_string(),
+        ));
+    }
+
+    let (read, write) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+    Ok((high(read)?, high(write)?))
+}
+
+// used by a forked shell, so that it does not hold on to the files and pipes of its parent.
+pub fn close_high() {
+    let fds = match fs::read_dir("/dev/fd") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<RawFd>().ok())
+            .filter(|fd| *fd >= HIGH_FD)
+            .collect::<Vec<RawFd>>(),
+        Err(_) => return,
+    };
+
+    for fd in fds {
+        unsafe { libc::close(fd) };
+    }
+}
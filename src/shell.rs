@@ -1,39 +1,61 @@
+use crate::alias::Alias;
+use crate::arithmetic;
 use crate::builtin;
+use crate::builtin::command;
+use crate::builtin::hashtable::PathCache;
+use crate::builtin::jump;
+use crate::builtin::source;
+use crate::builtin::test;
+use crate::builtin::Builtin;
+use crate::builtin::Registry;
 use crate::error::*;
+use crate::glob;
 use crate::parser;
 use crate::parser::lexer::Lexer;
+use crate::parser::token::Token;
 use crate::parser::Parser;
+use crate::pattern;
 use crate::profile;
+use crate::redirect;
+use crate::redirect::RedirectTable;
 use crate::terminal::Terminal;
+use crate::variable::Value;
 use crate::variable::Variable;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
 use std::io;
 use std::io::Read;
 use std::io::Write;
+use std::iter::Peekable;
+use std::mem;
 use std::os::unix::io::AsRawFd;
-use std::os::unix::io::FromRawFd;
+use std::os::unix::io::RawFd;
+use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process;
+use std::rc::Rc;
+use std::str::Chars;
 
 pub struct Shell {
-    variable: Variable,
+    state: ShellState,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Shell {
     pub fn new() -> Self {
         Self {
-            variable: Variable::new(),
+            state: ShellState::from_environ(),
         }
     }
 
     pub fn initialize(&mut self) -> Result<&mut Self> {
-        let node = parse(profile::read()?)?;
-
-        self.variable = Evaluator::new(node)
-            .set_variable(self.variable.to_owned())
-            .eval()?
-            .take_variable();
+        eval(&mut self.state, profile::read()?)?;
 
         Ok(self)
     }
@@ -46,80 +68,304 @@ impl Shell {
 
     fn rep(&mut self) {
         let prompt = parser::prompt::parse(
-            self.variable
+            self.state
+                .variable
                 .get("NSH_PROMPT".to_owned())
                 .unwrap_or(&String::default()),
         );
         let mut terminal = Terminal::new();
 
         terminal.prompt(prompt);
+        terminal.set_abbrs(self.state.alias.abbrs().to_owned());
+        terminal.set_jump_path(jump::path(&self.state.variable));
 
-        let source = match terminal.read_line() {
+        let mut source = match terminal.read_line() {
             Ok(string) => string,
             Err(err) => panic!("{err}"),
         };
+
+        // keep reading until every here-document has met its delimiter, and every case and for their end.
+        while !is_terminated(&source) {
+            terminal.prompt("> ".to_owned());
+
+            match terminal.read_line() {
+                Ok(string) => {
+                    source.push('\n');
+                    source.push_str(&string);
+                }
+                Err(err) => panic!("{err}"),
+            }
+        }
         drop(terminal);
 
-        match parse(source) {
-            Ok(node) => match Evaluator::new(node)
-                .set_variable(self.variable.to_owned())
-                .eval()
-            {
-                Ok(eval) => self.variable = eval.take_variable(),
-                Err(err) => {
-                    io::stderr()
-                        .lock()
-                        .write(format!("{err}\n").as_bytes())
-                        .unwrap();
+        match parse(source, &mut self.state) {
+            Ok(node) => {
+                let mut evaluator = Evaluator::new(node);
+                evaluator.set_state(mem::take(&mut self.state));
+
+                if let Err(err) = evaluator.eval() {
+                    evaluator.report(err);
                 }
-            },
+
+                self.state = evaluator.take_state();
+            }
             Err(err) => {
                 io::stderr()
                     .lock()
-                    .write(format!("{err}\n").as_bytes())
+                    .write_all(format!("{err}\n").as_bytes())
                     .unwrap();
             }
         }
     }
 }
 
-fn parse(source: String) -> Result<parser::Node> {
-    Parser::new(Lexer::new(
-        source
-            .replace('\n', " ;")
-            .replace('~', &env::var("HOME").unwrap_or("/".to_owned()))
-            .chars()
-            .collect(),
-    ))
-    .parse()
+// what the shell keeps from one command line to the next, which builtins can change.
+// it is moved in and out of the evaluator, not copied.
+pub struct ShellState {
+    pub variable: Variable,
+    pub alias: Alias,
+    pub builtins: Registry,
+    // the exit status of the last command, $?
+    pub status: i32,
+    // the files being sourced, outermost first, to find a file that sources itself
+    // and to report where a builtin failed.
+    pub sources: Vec<PathBuf>,
+    // where the commands were found in PATH.
+    pub hashed: PathCache,
+    // the directory stack of pushd and popd, below the current directory.
+    pub dirs: Vec<String>,
+}
+
+impl ToOwned for ShellState {
+    type Owned = ShellState;
+
+    fn to_owned(&self) -> Self::Owned {
+        Self {
+            variable: self.variable.to_owned(),
+            alias: self.alias.to_owned(),
+            builtins: self.builtins.to_owned(),
+            status: self.status,
+            sources: self.sources.to_owned(),
+            hashed: self.hashed.to_owned(),
+            dirs: self.dirs.to_owned(),
+        }
+    }
+}
+
+impl Default for ShellState {
+    fn default() -> Self {
+        Self {
+            variable: Variable::new(),
+            alias: Alias::new(),
+            builtins: Registry::default(),
+            status: 0,
+            sources: Vec::new(),
+            hashed: PathCache::default(),
+            dirs: Vec::new(),
+        }
+    }
+}
+
+impl ShellState {
+    // the variables start from the environment.
+    pub fn from_environ() -> Self {
+        Self {
+            variable: Variable::from_environ(),
+            ..Default::default()
+        }
+    }
+
+    pub fn value(&self, key: &str) -> Option<Value> {
+        if key == "?" {
+            return Some(Value::String(self.status.to_string()));
+        }
+
+        self.variable
+            .dynamic(key)
+            .or_else(|| self.variable.get_value(key).cloned())
+    }
+
+    // the value as a single string, the elements joined by spaces.
+    pub fn lookup(&self, key: &str) -> Option<String> {
+        self.value(key).map(|val| val.to_words().join(" "))
+    }
+
+    // =NAME val and =NAME[I] val. the value of an integer variable is evaluated as an expression.
+    pub fn assign(&mut self, key: String, val: Value) -> Result<()> {
+        let (name, index) = match key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+            Some((name, index)) => (name.to_owned(), Some(index.to_owned())),
+            None => (key, None),
+        };
+
+        let val = match val {
+            Value::String(string) if self.variable.attributes(&name).is_integer => {
+                Value::String(arithmetic::eval(&string, self)?.to_string())
+            }
+            val => val,
+        };
+
+        let index = match index {
+            Some(index) => index,
+            None => return self.variable.insert_value(name, val),
+        };
+
+        match val {
+            Value::String(string) => self.variable.insert_element(name, &index, string),
+            _ => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!("only a string can be set to {name}[{index}]"),
+            )),
+        }
+    }
+
+    // the builtin of the name, unless NSH_BC_[NAME] turns it off, e.g. =NSH_BC_CD off
+    pub fn builtin(&self, name: &str) -> Option<Rc<dyn Builtin>> {
+        let key = format!("NSH_BC_{}", name.to_uppercase().replace('-', "_"));
+
+        if let Some(val) = self.lookup(&key) {
+            if matches!(val.to_lowercase().as_str(), "0" | "off" | "false" | "no") {
+                return None;
+            }
+        }
+
+        self.builtins.get(name)
+    }
+}
+
+impl arithmetic::Scope for ShellState {
+    fn get(&self, key: &str) -> Option<String> {
+        self.lookup(key)
+    }
+
+    fn set(&mut self, key: &str, val: String) -> Result<()> {
+        self.variable.insert(key.to_owned(), val)
+    }
+}
+
+// runs the source in the state as the profile is run, e.g. for source and eval.
+// each command line is parsed after the ones before it have run, with the aliases they left.
+pub fn eval(state: &mut ShellState, source: String) -> Result<()> {
+    let mut parser = parser(source, state);
+
+    loop {
+        let node = match parser.parse_next() {
+            Ok(Some(node)) => node,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                state.variable.set_line(parser.line());
+                return Err(err);
+            }
+        };
+
+        let mut evaluator = Evaluator::new(node);
+
+        let result = evaluator.set_state(mem::take(state)).eval().map(|_| ());
+
+        *state = evaluator.take_state();
+
+        result?;
+
+        parser.set_alias(state.alias.to_owned());
+    }
+}
+
+// a syntax error leaves its line in $LINENO, like a command that failed, e.g. for source.
+fn parse(source: String, state: &mut ShellState) -> Result<parser::Node> {
+    let mut parser = parser(source, state);
+
+    parser
+        .parse()
+        .inspect_err(|_| state.variable.set_line(parser.line()))
+}
+
+fn parser(source: String, state: &ShellState) -> Parser {
+    let mut lexer = Lexer::new(expand_tilde(&source).chars().collect());
+    lexer.set_alias(state.alias.to_owned());
+
+    Parser::new(lexer)
+}
+
+// ~ becomes HOME at the start of a word or after : and NAME=, e.g. ~/bin, =PATH ~/bin:~/.cargo/bin.
+// elsewhere it is left alone, e.g. the =~ operator of [[ ]].
+fn expand_tilde(source: &str) -> String {
+    let home = env::var("HOME").unwrap_or("/".to_owned());
+
+    let chars = source.chars().collect::<Vec<char>>();
+
+    let mut buffer = String::new();
+
+    for (i, ch) in chars.iter().enumerate() {
+        let prev = |n: usize| match i.checked_sub(n) {
+            Some(j) => chars[j],
+            None => ' ',
+        };
+
+        let is_start = prev(1).is_whitespace()
+            || prev(1) == ':'
+            || (prev(1) == '=' && !prev(2).is_whitespace());
+
+        let is_end = match chars.get(i + 1) {
+            Some(next) => next.is_whitespace() || matches!(next, '/' | ':' | ';'),
+            None => true,
+        };
+
+        if *ch == '~' && is_start && is_end {
+            buffer.push_str(&home);
+        } else {
+            buffer.push(*ch);
+        }
+    }
+
+    buffer
+}
+
+fn is_terminated(source: &str) -> bool {
+    let mut lexer = Lexer::new(source.chars().collect());
+
+    // the number of case and for that are still waiting for esac and done.
+    let mut depth = 0;
+
+    for token in lexer.by_ref() {
+        match token {
+            Token::Case | Token::For => depth += 1,
+            Token::Esac | Token::Done => depth -= 1,
+            _ => {}
+        }
+    }
+
+    lexer.is_terminated() && depth <= 0
 }
 
 struct Evaluator {
     node: parser::Node,
-    variable: Variable,
-    stdin: Option<process::Stdio>,
-    stdout: Option<process::Stdio>,
-    stderr: Option<process::Stdio>,
+    state: ShellState,
+    stdin: Option<File>,
+    stdout: Option<File>,
+    // the stages of the pipeline being started, waited for once all of them are running.
+    pipeline: Option<Vec<libc::pid_t>>,
+    // forked by process substitution, reaped once a command is waited for.
+    children: Vec<libc::pid_t>,
 }
 
 impl Evaluator {
     pub fn new(node: parser::Node) -> Self {
         Self {
-            node: node,
-            variable: Variable::new(),
+            node,
+            state: ShellState::default(),
             stdin: None,
             stdout: None,
-            stderr: None,
+            pipeline: None,
+            children: Vec::new(),
         }
     }
 
-    pub fn set_variable(&mut self, variable: Variable) -> &mut Self {
-        self.variable = variable;
+    pub fn set_state(&mut self, state: ShellState) -> &mut Self {
+        self.state = state;
         self
     }
 
-    pub fn take_variable(&mut self) -> Variable {
-        self.variable.to_owned()
+    pub fn take_state(&mut self) -> ShellState {
+        mem::take(&mut self.state)
     }
 
     pub fn eval(&mut self) -> Result<&mut Self> {
@@ -133,35 +379,106 @@ impl Evaluator {
             }
 
             parser::Node::Pipe(mut pipe) => {
+                self.pipeline = Some(Vec::new());
+
+                let mut result = Ok(());
+
                 while let Some(node) = pipe.take() {
+                    let mut next_stdin = None;
+
                     if pipe.is_pipe() {
-                        self.stdout = Some(process::Stdio::piped())
+                        let (read, write) = redirect::pipe()?;
+                        self.stdout = Some(write);
+                        next_stdin = Some(read);
                     }
 
                     self.node = node;
-                    self.eval()?;
+                    result = self.eval().map(|_| ());
+
+                    // drops the ends the stage did not take, so the next stage can see EOF.
+                    self.stdout = None;
+                    self.stdin = next_stdin;
+
+                    if result.is_err() {
+                        break;
+                    }
+                }
+
+                self.stdin = None;
+
+                for pid in self.pipeline.take().unwrap_or_default() {
+                    self.state.status = wait(pid)?;
                 }
+
+                self.reap();
+
+                result?;
             }
 
             parser::Node::Command(command) => self.run_command(command)?,
 
-            parser::Node::Insert(mut insert) => {
-                let key = match insert.take_key() {
-                    Some(node) => match node {
-                        parser::Node::String(string) => string,
-                        _ => Err(Error::new(ErrorKind::ExecutionFailed, "".to_owned()))?,
-                    },
-                    None => return Ok(self),
-                };
+            parser::Node::Group(group) => {
+                self.state.variable.set_line(group.line());
+                self.run_group(group)?
+            }
+
+            parser::Node::Case(case) => {
+                self.state.variable.set_line(case.line());
+                self.run_case(case)?
+            }
+
+            parser::Node::Include(node, line) => {
+                self.state.variable.set_line(line);
+
+                let file = self.word(*node)?;
+
+                source::source(&mut self.state, &file, None)?;
+            }
+
+            parser::Node::Conditional(words, line) => {
+                self.state.variable.set_line(line);
+                self.state.status = (!self.conditional(&words)?) as i32;
+            }
+
+            // the status is 0 when the expression is not 0, like in sh.
+            parser::Node::Let(string, line) => {
+                self.state.variable.set_line(line);
+                self.state.status = (arithmetic::eval(&string, self)? == 0) as i32;
+            }
+
+            parser::Node::AndOr(mut and_or) => {
+                if let Some(node) = and_or.take_left() {
+                    self.node = node;
+
+                    if let Err(err) = self.eval() {
+                        self.report(err);
+                    }
+                }
 
-                let val = match insert.take_val() {
-                    Some(node) => match node {
-                        parser::Node::String(string) => string,
-                        _ => Err(Error::new(ErrorKind::ExecutionFailed, "".to_owned()))?,
-                    },
-                    None => return Ok(self),
+                let is_run = match and_or.kind() {
+                    parser::AndOrKind::And => self.state.status == 0,
+                    parser::AndOrKind::Or => self.state.status != 0,
                 };
-                self.variable.insert(key, val);
+
+                if is_run {
+                    if let Some(node) = and_or.take_right() {
+                        self.node = node;
+                        self.eval()?;
+                    }
+                }
+            }
+
+            parser::Node::Insert(insert) => {
+                self.state.variable.set_line(insert.line());
+
+                if let Some((key, val)) = self.evaluate_insert(insert)? {
+                    self.assign(key, val)?;
+                }
+            }
+
+            parser::Node::For(for_loop) => {
+                self.state.variable.set_line(for_loop.line());
+                self.run_for(for_loop)?
             }
 
             _ => {}
@@ -171,21 +488,64 @@ impl Evaluator {
     }
 
     fn run_command(&mut self, mut command: parser::Command) -> Result<()> {
+        self.state.variable.set_line(command.line());
+
+        let assigns = command.take_assigns();
+
+        if assigns.is_empty() {
+            return self.run_simple_command(command);
+        }
+
+        // NAME=val cmd, the variables are exported to the command and put back after it.
+        let mut saved = Vec::new();
+
+        let mut result = Ok(());
+
+        for node in assigns {
+            if let parser::Node::Insert(insert) = node {
+                result = match self.evaluate_insert(insert) {
+                    Ok(Some((key, val))) => {
+                        saved.push((key.to_owned(), self.state.variable.entry(&key)));
+
+                        self.state.variable.attributes_mut(&key).is_exported = true;
+
+                        self.assign(key, val)
+                    }
+                    Ok(None) => Ok(()),
+                    Err(err) => Err(err),
+                };
+
+                if result.is_err() {
+                    break;
+                }
+            }
+        }
+
+        if result.is_ok() {
+            result = self.run_simple_command(command);
+        }
+
+        for (key, entry) in saved.into_iter().rev() {
+            self.state.variable.restore(key, entry);
+        }
+
+        result
+    }
+
+    fn run_simple_command(&mut self, mut command: parser::Command) -> Result<()> {
         let (mut program, mut args, mut is_background): (String, Vec<String>, bool) =
             (String::default(), Vec::default(), false);
 
+        let mut redirect_table = RedirectTable::new();
+
         if let Some(prefix) = command.take_prefix() {
             match prefix {
-                parser::Node::String(string) => {
-                    program = string;
-                }
+                parser::Node::String(_) | parser::Node::Reference(_) => {
+                    // the other words of $cmd, e.g. =cmd (ls -l), come before the arguments.
+                    let mut words = self.words(prefix)?.into_iter();
 
-                parser::Node::Reference(key) => {
-                    program = self
-                        .variable
-                        .get(key)
-                        .unwrap_or(&String::default())
-                        .to_owned();
+                    program = words.next().unwrap_or_default();
+                    args.extend(words);
                 }
                 _ => Err(Error::new(ErrorKind::ExecutionFailed, "".to_owned()))?,
             }
@@ -200,70 +560,24 @@ impl Evaluator {
         if let Some(mut suffix) = command.take_suffix() {
             while let Some(node) = suffix.take() {
                 match node {
-                    parser::Node::String(string) => args.push(string),
-                    parser::Node::Reference(key) => match env::var_os(&key) {
-                        Some(val) => args.push(val.to_string_lossy().to_string()),
-                        None => {
-                            if let Some(val) = self.variable.get(key) {
-                                args.push(val.to_owned())
-                            }
-                        }
-                    },
-                    parser::Node::Redirect(mut redirect) => {
-                        let left_fd = match redirect.take_left() {
-                            Some(left) => match *left {
-                                parser::Node::FD(fd) => fd,
-                                _ => continue,
-                            },
-                            None => {
-                                continue;
-                            }
-                        };
-
-                        let right_file = match redirect.take_right() {
-                            Some(right) => match *right {
-                                parser::Node::String(string) => {
-                                    match File::options()
-                                        .create(true)
-                                        .read(true)
-                                        .write(true)
-                                        .open(string)
-                                    {
-                                        Ok(file) => file,
-                                        Err(err) => {
-                                            Err(Error::new(ErrorKind::OpenFailed, err.to_string()))?
-                                        }
-                                    }
-                                }
-
-                                parser::Node::FD(fd) => unsafe { File::from_raw_fd(fd as i32) },
-                                _ => {
-                                    continue;
-                                }
-                            },
-                            None => {
-                                continue;
-                            }
-                        };
+                    parser::Node::String(_)
+                    | parser::Node::Reference(_)
+                    | parser::Node::Arithmetic(_) => args.extend(self.words(node)?),
 
-                        if matches!(left_fd, 0 | 1 | 2) == false {
-                            unsafe {
-                                libc::dup2(left_fd as i32, right_file.as_raw_fd());
-                            }
+                    parser::Node::Insert(insert) => {
+                        if let Some((key, val)) = self.evaluate_insert(insert)? {
+                            args.push(format!("{key}={}", val.to_words().join(" ")));
                         }
+                    }
 
-                        match redirect.kind() {
-                            parser::RedirectKind::Input => {
-                                self.stdin = Some(process::Stdio::from(right_file));
-                            }
-                            parser::RedirectKind::Output => {
-                                if left_fd == 2 {
-                                    self.stderr = Some(process::Stdio::from(right_file));
-                                } else {
-                                    self.stdout = Some(process::Stdio::from(right_file));
-                                }
-                            }
-                        }
+                    parser::Node::Redirect(redirect) => {
+                        self.redirect(&mut redirect_table, redirect)?
+                    }
+
+                    parser::Node::ProcessSubstitution(substitution) => {
+                        let file = self.substitute(substitution)?;
+                        let fd = redirect_table.pass(file);
+                        args.push(format!("/dev/fd/{fd}"));
                     }
 
                     parser::Node::Background(_) => {
@@ -274,50 +588,79 @@ impl Evaluator {
             }
         }
 
-        match program.as_str() {
-            "abort" => builtin::abort(),
-            "exit" => {
-                let code = match args.pop().unwrap_or("0".to_owned()).parse::<i32>() {
-                    Ok(code) => code,
-                    Err(_) => Err(Error::new(
-                        ErrorKind::ExecutionFailed,
-                        format!("only i32 is allowed for the exit argument"),
-                    ))?,
-                };
-                builtin::exit(code);
+        // command NAME runs NAME, which the lexer has not taken as an alias.
+        // command -v and -V are left to the builtin.
+        if program == "command"
+            && args
+                .first()
+                .is_some_and(|arg| !matches!(arg.as_str(), "-v" | "-V"))
+        {
+            if args[0] == "--" {
+                args.remove(0);
+            }
+
+            if args.is_empty() {
+                return Ok(());
             }
-            "cd" => {
-                if let Err(err) = builtin::cd(args.pop().unwrap_or("./".to_owned())) {
+
+            program = args.remove(0);
+        }
+
+        let is_shell = self.pipeline.is_none() && self.stdin.is_none() && self.stdout.is_none();
+
+        match program.as_str() {
+            // exec without a command changes the files of the shell itself rather than running in it.
+            "exec" if args.is_empty() && is_shell && self.state.builtin("exec").is_some() => {
+                if let Err(err) = redirect_table.apply() {
                     Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?
                 }
             }
             _ => {
-                match process::Command::new(&program)
-                    .args(args)
-                    .env(
-                        "PATH",
-                        self.variable
-                            .get("PATH".to_owned())
-                            .unwrap_or(&String::default())
-                            .to_owned(),
-                    )
-                    .stdin(self.stdin.take().unwrap_or(process::Stdio::inherit()))
-                    .stdout(self.stdout.take().unwrap_or(process::Stdio::inherit()))
-                    .stderr(self.stderr.take().unwrap_or(process::Stdio::inherit()))
-                    .spawn()
-                {
-                    Ok(mut child) => {
-                        if let Some(stdout) = child.stdout {
-                            self.stdin = Some(process::Stdio::from(stdout));
-                        } else {
-                            if is_background == false {
-                                if let Err(err) = child.wait() {
-                                    Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?
-                                }
-                            }
-                        }
+                if let Some(builtin) = self.state.builtin(&program) {
+                    return self.run_builtin(builtin, args, redirect_table, is_background);
+                }
+
+                // not found is told before forking, and without a search of PATH when it is hashed.
+                let path = match command::lookup(&mut self.state, &program) {
+                    Some(path) => path,
+                    None => {
+                        self.state.status = 127;
+
+                        Err(Error::new(
+                            ErrorKind::NotFound,
+                            format!("nsh command not found: {}", program),
+                        ))?
                     }
+                };
+
+                self.state.hashed.hit(&program);
+
+                let mut process = process::Command::new(path);
+
+                process.arg0(&program);
+
+                if !redirect_table.is_empty() {
+                    unsafe {
+                        process.pre_exec(move || redirect_table.apply());
+                    }
+                }
+
+                let spawned = process
+                    .args(args)
+                    .env_clear()
+                    .envs(self.state.variable.environ())
+                    .stdin(stdio(self.stdin.take()))
+                    .stdout(stdio(self.stdout.take()))
+                    .spawn();
+
+                // closes the parent's copies of the files in the redirect table.
+                drop(process);
+
+                match spawned {
+                    Ok(child) => self.started(child.id() as libc::pid_t, is_background)?,
                     Err(err) => {
+                        self.state.status = 127;
+
                         if err.kind() == io::ErrorKind::NotFound {
                             Err(Error::new(
                                 ErrorKind::NotFound,
@@ -333,6 +676,898 @@ impl Evaluator {
 
         Ok(())
     }
+
+    fn run_group(&mut self, mut group: parser::Group) -> Result<()> {
+        let node = match group.take_node() {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+
+        let mut redirect_table = RedirectTable::new();
+
+        let mut is_background = false;
+
+        if let Some(mut suffix) = group.take_suffix() {
+            while let Some(node) = suffix.take() {
+                match node {
+                    parser::Node::Redirect(redirect) => {
+                        self.redirect(&mut redirect_table, redirect)?
+                    }
+                    parser::Node::Background(_) => is_background = true,
+                    _ => {}
+                }
+            }
+        }
+
+        // a brace group runs in this shell unless it has to run alongside it,
+        // with its redirections applied to the shell itself until it ends.
+        if matches!(group.kind(), parser::GroupKind::Brace)
+            && self.pipeline.is_none()
+            && !is_background
+        {
+            let saved = redirect_table.apply_shell()?;
+
+            // the block is a scope of its own for local variables.
+            self.state.variable.push_scope();
+
+            self.node = node;
+            let result = self.eval().map(|_| ());
+
+            self.state.variable.pop_scope();
+
+            saved.restore();
+
+            return result;
+        }
+
+        let (stdin, stdout) = (self.stdin.take(), self.stdout.take());
+
+        let pid = self.fork_node(node, stdin, stdout, redirect_table)?;
+
+        self.started(pid, is_background)
+    }
+
+    // a compound command that is a stage of a pipeline runs alongside the shell, like a group.
+    fn run_stage(&mut self, node: parser::Node) -> Result<()> {
+        let (stdin, stdout) = (self.stdin.take(), self.stdout.take());
+
+        let pid = self.fork_node(node, stdin, stdout, RedirectTable::new())?;
+
+        self.started(pid, false)
+    }
+
+    fn run_for(&mut self, mut for_loop: parser::For) -> Result<()> {
+        if self.pipeline.is_some() {
+            return self.run_stage(parser::Node::For(for_loop));
+        }
+
+        let mut words = Vec::new();
+
+        for node in for_loop.take_words() {
+            words.extend(self.words(node)?);
+        }
+
+        let node = match for_loop.take_node() {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+
+        // 0 when the body never runs, like in sh.
+        self.state.status = 0;
+
+        for word in words {
+            self.state.variable.insert(for_loop.name().to_owned(), word)?;
+
+            self.node = node.to_owned();
+            self.eval()?;
+        }
+
+        Ok(())
+    }
+
+    fn run_case(&mut self, mut case: parser::Case) -> Result<()> {
+        if self.pipeline.is_some() {
+            return self.run_stage(parser::Node::Case(case));
+        }
+
+        let word = match case.take_word() {
+            Some(node) => self.word(node)?,
+            None => return Ok(()),
+        };
+
+        // 0 when no pattern matches, like in sh.
+        self.state.status = 0;
+
+        let mut is_fall_through = false;
+
+        for mut item in case.take_items() {
+            if !is_fall_through {
+                let mut is_match = false;
+
+                for pattern in item.patterns() {
+                    if glob::is_match(&self.word(pattern.to_owned())?, &word) {
+                        is_match = true;
+                        break;
+                    }
+                }
+
+                if !is_match {
+                    continue;
+                }
+            }
+
+            if let Some(node) = item.take_node() {
+                self.node = node;
+                self.eval()?;
+            }
+
+            match item.terminator() {
+                parser::CaseTerminator::Break => break,
+                parser::CaseTerminator::FallThrough => is_fall_through = true,
+                parser::CaseTerminator::Continue => is_fall_through = false,
+            }
+        }
+
+        Ok(())
+    }
+
+    // [[ ... ]], the tests of test joined by ( ) ! && and ||, which stop as soon as the result is known.
+    // the words are not split. == and != match a glob pattern, the integer operators take
+    // arithmetic expressions, and =~ matches a regex, leaving the match in NSH_REGEX.
+    fn conditional(&mut self, words: &[String]) -> Result<bool> {
+        let mut i = 0;
+
+        let is_true = self.conditional_or(words, &mut i, true)?;
+
+        match words.get(i) {
+            Some(word) => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("[[: unexpected word: {word}"),
+            )),
+            None => Ok(is_true),
+        }
+    }
+
+    // what is not evaluated is still read, but not expanded.
+    fn conditional_or(&mut self, words: &[String], i: &mut usize, is_eval: bool) -> Result<bool> {
+        let mut is_true = self.conditional_and(words, i, is_eval)?;
+
+        while words.get(*i).map(String::as_str) == Some("||") {
+            *i += 1;
+
+            let right = self.conditional_and(words, i, is_eval && !is_true)?;
+
+            is_true = is_true || right;
+        }
+
+        Ok(is_true)
+    }
+
+    fn conditional_and(&mut self, words: &[String], i: &mut usize, is_eval: bool) -> Result<bool> {
+        let mut is_true = self.conditional_not(words, i, is_eval)?;
+
+        while words.get(*i).map(String::as_str) == Some("&&") {
+            *i += 1;
+
+            let right = self.conditional_not(words, i, is_eval && is_true)?;
+
+            is_true = is_true && right;
+        }
+
+        Ok(is_true)
+    }
+
+    fn conditional_not(&mut self, words: &[String], i: &mut usize, is_eval: bool) -> Result<bool> {
+        match words.get(*i).map(String::as_str) {
+            Some("!") => {
+                *i += 1;
+                Ok(!self.conditional_not(words, i, is_eval)?)
+            }
+            _ => self.conditional_primary(words, i, is_eval),
+        }
+    }
+
+    fn conditional_primary(
+        &mut self,
+        words: &[String],
+        i: &mut usize,
+        is_eval: bool,
+    ) -> Result<bool> {
+        let word = |n: usize| words.get(*i + n).map(String::as_str);
+
+        let is_operand = |word: Option<&str>| match word {
+            Some(word) => !matches!(word, "&&" | "||" | ")"),
+            None => false,
+        };
+
+        match (word(0), word(1)) {
+            (None, _) => Err(Error::new(
+                ErrorKind::WrongSyntax,
+                format!("[[: expression expected: [[ {} ]]", words.join(" ")),
+            )),
+
+            (Some("("), _) => {
+                *i += 1;
+
+                let is_true = self.conditional_or(words, i, is_eval)?;
+
+                match words.get(*i).map(String::as_str) {
+                    Some(")") => {
+                        *i += 1;
+                        Ok(is_true)
+                    }
+                    _ => Err(Error::new(
+                        ErrorKind::WrongSyntax,
+                        format!("[[: ) expected: [[ {} ]]", words.join(" ")),
+                    )),
+                }
+            }
+
+            (Some(left), Some(op))
+                if (test::is_binary(op) || op == "=~") && is_operand(word(2)) =>
+            {
+                let right = words[*i + 2].as_str();
+
+                *i += 3;
+
+                match is_eval {
+                    true => self.conditional_binary(left, op, right),
+                    false => Ok(false),
+                }
+            }
+
+            (Some(op), next) if test::is_unary(op) && is_operand(next) => {
+                *i += 2;
+
+                match is_eval {
+                    true => test::unary(
+                        op,
+                        &self.conditional_word(next.unwrap_or_default(), ToOwned::to_owned)?,
+                    ),
+                    false => Ok(false),
+                }
+            }
+
+            (Some(word), _) => {
+                *i += 1;
+
+                match is_eval {
+                    true => Ok(!self.conditional_word(word, ToOwned::to_owned)?.is_empty()),
+                    false => Ok(false),
+                }
+            }
+        }
+    }
+
+    fn conditional_binary(&mut self, left: &str, op: &str, right: &str) -> Result<bool> {
+        let left = self.conditional_word(left, ToOwned::to_owned)?;
+
+        match op {
+            "==" | "=" | "!=" => {
+                let right = self.conditional_word(right, glob::escape)?;
+
+                Ok(glob::is_match(&right, &left) == (op != "!="))
+            }
+
+            "=~" => {
+                let right = self.conditional_word(right, regex::escape)?;
+
+                let captures = pattern::captures(&right, &left)?;
+
+                self.set_regex(captures.as_deref());
+
+                Ok(captures.is_some())
+            }
+
+            "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge" => {
+                let right = self.conditional_word(right, ToOwned::to_owned)?;
+
+                let left = arithmetic::eval(&left, self)?;
+                let right = arithmetic::eval(&right, self)?;
+
+                test::binary(&left.to_string(), op, &right.to_string())
+            }
+
+            _ => {
+                let right = self.conditional_word(right, ToOwned::to_owned)?;
+
+                test::binary(&left, op, &right)
+            }
+        }
+    }
+
+    // expands a word of [[ ... ]] without splitting it.
+    // the quoted parts go through quote, so that they stay literal in a pattern.
+    fn conditional_word(&mut self, word: &str, quote: fn(&str) -> String) -> Result<String> {
+        let mut buffer = String::new();
+
+        let mut chars = word.chars().peekable();
+
+        let mut unquoted = String::new();
+
+        while let Some(ch) = chars.next() {
+            if !matches!(ch, '\'' | '"') {
+                unquoted.push(ch);
+
+                if ch == '\\' {
+                    if let Some(ch) = chars.next() {
+                        unquoted.push(ch);
+                    }
+                }
+
+                continue;
+            }
+
+            buffer.push_str(&self.expand(&unquoted)?);
+            unquoted.clear();
+
+            let mut quoted = String::new();
+
+            for quoted_ch in chars.by_ref() {
+                if quoted_ch == ch {
+                    break;
+                }
+
+                quoted.push(quoted_ch);
+            }
+
+            match ch {
+                '"' => buffer.push_str(&quote(&self.expand(&quoted)?)),
+                _ => buffer.push_str(&quote(&quoted)),
+            }
+        }
+
+        buffer.push_str(&self.expand(&unquoted)?);
+
+        Ok(buffer)
+    }
+
+    // $NSH_REGEX[0] is the whole match and $NSH_REGEX[N] the Nth group, unset when nothing matched.
+    fn set_regex(&mut self, captures: Option<&[String]>) {
+        match captures {
+            Some(captures) => self
+                .state
+                .variable
+                .insert_value("NSH_REGEX".to_owned(), Value::Array(captures.to_vec())),
+            None => self.state.variable.remove("NSH_REGEX".to_owned()),
+        }
+        .ok();
+    }
+
+    // the name and value of =NAME val, NAME=val and NAME=(...)
+    fn evaluate_insert(&mut self, mut insert: parser::Insert) -> Result<Option<(String, Value)>> {
+        let key = match insert.take_key() {
+            Some(node) => match node {
+                parser::Node::String(string) => string,
+                _ => Err(Error::new(ErrorKind::ExecutionFailed, "".to_owned()))?,
+            },
+            None => return Ok(None),
+        };
+
+        let val = match insert.take_val() {
+            Some(node) => match node {
+                // =B $A copies the value of A as it is, an array stays an array.
+                parser::Node::Reference(name) if self.value(&name).is_some() => {
+                    self.value(&name).unwrap()
+                }
+                parser::Node::String(_) | parser::Node::Reference(_) | parser::Node::Arithmetic(_) => {
+                    Value::String(self.word(node)?)
+                }
+                parser::Node::Array(nodes) => Value::Array(self.list(nodes)?),
+                parser::Node::Tuple(nodes) => Value::Tuple(self.list(nodes)?),
+                parser::Node::Map(entries) => {
+                    let mut map = BTreeMap::new();
+
+                    for (key, node) in entries {
+                        map.insert(key, self.words(node)?.join(" "));
+                    }
+
+                    Value::Map(map)
+                }
+                _ => Err(Error::new(ErrorKind::ExecutionFailed, "".to_owned()))?,
+            },
+            None => return Ok(None),
+        };
+
+        Ok(Some((key, val)))
+    }
+
+    // the index of =NAME[I] val is expanded first.
+    fn assign(&mut self, key: String, val: Value) -> Result<()> {
+        let key = match key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+            Some((name, index)) => format!("{name}[{}]", self.expand(index)?),
+            None => key,
+        };
+
+        self.state.assign(key, val)
+    }
+
+    fn list(&mut self, nodes: Vec<parser::Node>) -> Result<Vec<String>> {
+        let mut vals = Vec::new();
+
+        for node in nodes {
+            vals.extend(self.words(node)?);
+        }
+
+        Ok(vals)
+    }
+
+    // the words of a string, a reference or $(( )). every element of an array is a word of its own.
+    fn words(&mut self, node: parser::Node) -> Result<Vec<String>> {
+        match node {
+            parser::Node::String(string) => Ok(vec![self.expand(&string)?]),
+            parser::Node::Reference(key) => self.values(&key),
+            parser::Node::Arithmetic(string) => {
+                Ok(vec![arithmetic::eval(&string, self)?.to_string()])
+            }
+            _ => Err(Error::new(ErrorKind::Internal, "internal error".to_owned())),
+        }
+    }
+
+    // the string of a word such as the subject and the patterns of case.
+    fn word(&mut self, node: parser::Node) -> Result<String> {
+        Ok(self.words(node)?.join(" "))
+    }
+
+    // a command or group has been started as pid.
+    fn started(&mut self, pid: libc::pid_t, is_background: bool) -> Result<()> {
+        if is_background {
+            self.state.variable.set_background(pid);
+        }
+
+        match self.pipeline.as_mut() {
+            Some(pids) => pids.push(pid),
+            None => {
+                if !is_background {
+                    self.state.status = wait(pid)?;
+                    self.reap();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn report(&mut self, err: Error) {
+        if !matches!(err.kind(), ErrorKind::NotFound) {
+            self.state.status = 1;
+        }
+
+        io::stderr()
+            .lock()
+            .write_all(format!("{err}\n").as_bytes())
+            .ok();
+    }
+
+    fn redirect(
+        &mut self,
+        redirect_table: &mut RedirectTable,
+        mut redirect: parser::Redirect,
+    ) -> Result<()> {
+        let left_fd = match redirect.take_left() {
+            Some(left) => match *left {
+                parser::Node::FD(fd) => fd as RawFd,
+                _ => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+
+        if let Some(right) = redirect.take_right() {
+            match *right {
+                parser::Node::ProcessSubstitution(substitution) => {
+                    let file = self.substitute(substitution)?;
+                    redirect_table.file(left_fd, file)
+                }
+                parser::Node::HereDoc(here_doc) => {
+                    if here_doc.is_expand() {
+                        redirect_table.here(left_fd, &self.expand(here_doc.body())?)?
+                    } else {
+                        redirect_table.here(left_fd, here_doc.body())?
+                    }
+                }
+                node @ (parser::Node::String(_) | parser::Node::Reference(_)) => {
+                    let string = self.word(node)?;
+
+                    match redirect.kind() {
+                        parser::RedirectKind::HereString => {
+                            redirect_table.here(left_fd, &format!("{string}\n"))?
+                        }
+                        kind => redirect_table.open(left_fd, &string, kind)?,
+                    }
+                }
+                parser::Node::FD(fd) => redirect_table.dup(left_fd, fd as RawFd),
+                parser::Node::CloseFD(_) => redirect_table.close(left_fd),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // starts the command of <(cmd) or >(cmd) in a forked shell connected to a pipe,
+    // and returns the other end of the pipe.
+    fn substitute(&mut self, mut substitution: parser::ProcessSubstitution) -> Result<File> {
+        let node = match substitution.take_node() {
+            Some(node) => node,
+            None => Err(Error::new(ErrorKind::Internal, "internal error".to_owned()))?,
+        };
+
+        let (read, write) = redirect::pipe()?;
+
+        let (pid, file) = match substitution.kind() {
+            parser::SubstitutionKind::Input => (
+                self.fork_node(node, None, Some(write), RedirectTable::new())?,
+                read,
+            ),
+            parser::SubstitutionKind::Output => (
+                self.fork_node(node, Some(read), None, RedirectTable::new())?,
+                write,
+            ),
+        };
+
+        self.children.push(pid);
+
+        Ok(file)
+    }
+
+    // evaluates the node in a copy of the shell.
+    fn fork_node(
+        &self,
+        node: parser::Node,
+        stdin: Option<File>,
+        stdout: Option<File>,
+        redirect_table: RedirectTable,
+    ) -> Result<libc::pid_t> {
+        fork(stdin, stdout, redirect_table, || {
+            let mut evaluator = Evaluator::new(node);
+            evaluator.set_state(self.state.to_owned());
+
+            if let Err(err) = evaluator.eval() {
+                evaluator.report(err);
+            }
+
+            evaluator.state.status
+        })
+    }
+
+    // runs a builtin in this shell, with its redirections applied to the shell until it returns.
+    // it is forked when it has to run alongside the shell, e.g. as a stage of a pipeline.
+    fn run_builtin(
+        &mut self,
+        builtin: Rc<dyn Builtin>,
+        args: Vec<String>,
+        redirect_table: RedirectTable,
+        is_background: bool,
+    ) -> Result<()> {
+        if self.pipeline.is_none()
+            && self.stdin.is_none()
+            && self.stdout.is_none()
+            && !is_background
+        {
+            let saved = redirect_table.apply_shell()?;
+
+            let location = source::location(&self.state);
+
+            let status = builtin.run(
+                args,
+                &mut builtin::Stdin,
+                &mut io::stdout(),
+                &mut source::Located::new(io::stderr(), location),
+                &mut self.state,
+            );
+
+            io::stdout().flush().ok();
+
+            saved.restore();
+
+            self.state.status = status;
+
+            return Ok(());
+        }
+
+        let (stdin, stdout) = (self.stdin.take(), self.stdout.take());
+
+        let location = source::location(&self.state);
+
+        let state = &mut self.state;
+
+        let pid = fork(stdin, stdout, redirect_table, || {
+            builtin.run(
+                args,
+                &mut builtin::Stdin,
+                &mut io::stdout(),
+                &mut source::Located::new(io::stderr(), location),
+                state,
+            )
+        })?;
+
+        self.started(pid, is_background)
+    }
+
+    fn reap(&mut self) {
+        for pid in self.children.drain(..) {
+            unsafe { libc::waitpid(pid, std::ptr::null_mut(), 0) };
+        }
+    }
+
+    fn value(&self, key: &str) -> Option<Value> {
+        self.state.value(key)
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        self.state.lookup(key)
+    }
+
+    // a reference as a single string.
+    fn reference(&mut self, key: &str) -> Result<String> {
+        Ok(self.values(key)?.join(" "))
+    }
+
+    // $NAME, ${NAME}, ${NAME/pattern/replacement}, $NAME[I], $NAME[I:J], $#NAME and $!NAME
+    fn values(&mut self, key: &str) -> Result<Vec<String>> {
+        let (key, is_braced) = match key.strip_prefix('{').and_then(|key| key.strip_suffix('}')) {
+            Some(key) => (key, true),
+            None => (key, false),
+        };
+
+        if let Some((name, pattern, replacement, is_all)) = pattern::parse_substitution(key)
+            .filter(|_| is_braced)
+        {
+            return self
+                .values(name)?
+                .iter()
+                .map(|val| pattern::replace(val, &pattern, replacement, is_all))
+                .collect();
+        }
+
+        // the length, and the indexes or keys.
+        if let Some(name) = key.strip_prefix('#').filter(|name| !name.is_empty()) {
+            let len = self.value(name).map(|val| val.len()).unwrap_or(0);
+
+            return Ok(vec![len.to_string()]);
+        }
+
+        if let Some(name) = key.strip_prefix('!').filter(|name| !name.is_empty()) {
+            return Ok(self.value(name).map(|val| val.keys()).unwrap_or_default());
+        }
+
+        if let Some((name, index)) = key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+            let value = match self.value(name) {
+                Some(value) => value,
+                None => return Ok(Vec::new()),
+            };
+
+            let index = self.expand(index)?;
+
+            return match index.split_once(':') {
+                Some((start, end)) if !matches!(value, Value::Map(_)) => {
+                    value.slice(start, end)
+                }
+                _ => Ok(value.get(&index)?.into_iter().collect()),
+            };
+        }
+
+        Ok(self.value(key).map(|val| val.to_words()).unwrap_or_default())
+    }
+
+    // expands $NAME, $?, ${...}, $(cmd) and $(( expression )) in a string or the body of a here-document.
+    // a backslash keeps $, ` and \ literal, and joins a line with the next one.
+    fn expand(&mut self, source: &str) -> Result<String> {
+        let mut buffer = String::new();
+
+        let mut chars = source.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.peek() {
+                    Some('$' | '`' | '\\') => buffer.push(chars.next().unwrap()),
+                    Some('\n') => {
+                        chars.next();
+                    }
+                    _ => buffer.push(ch),
+                },
+
+                '$' => {
+                    let mut key = String::new();
+
+                    if chars.peek() == Some(&'(') {
+                        let source = match enclosed(&mut chars, '(', ')') {
+                            Some(source) => source,
+                            None => Err(Error::new(
+                                ErrorKind::WrongSyntax,
+                                "$( is not closed with )".to_owned(),
+                            ))?,
+                        };
+
+                        // $(( expression )) is $( followed by ( expression )
+                        let source = &source[1..source.len() - 1];
+
+                        match source
+                            .strip_prefix('(')
+                            .and_then(|source| source.strip_suffix(')'))
+                        {
+                            Some(expression) => buffer
+                                .push_str(&arithmetic::eval(expression, self)?.to_string()),
+                            None => buffer.push_str(&self.capture(source)?),
+                        }
+
+                        continue;
+                    }
+
+                    if chars.peek() == Some(&'{') {
+                        key = match enclosed(&mut chars, '{', '}') {
+                            Some(key) => key,
+                            None => Err(Error::new(
+                                ErrorKind::WrongSyntax,
+                                "${ is not closed with }".to_owned(),
+                            ))?,
+                        };
+                    } else if let Some(ch) =
+                        chars.next_if(|ch| matches!(ch, '?' | '$' | '!' | '#' | '@' | '*'))
+                    {
+                        key.push(ch);
+                    } else if let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit()) {
+                        // $0 ~ $9 are a single digit, e.g. $0s
+                        key.push(ch);
+                    } else {
+                        while let Some(ch) = chars.next_if(|ch| ch.is_alphanumeric() || *ch == '_')
+                        {
+                            key.push(ch);
+                        }
+                    }
+
+                    if key.is_empty() {
+                        buffer.push(ch);
+                    } else {
+                        buffer.push_str(&self.reference(&key)?);
+                    }
+                }
+
+                _ => buffer.push(ch),
+            }
+        }
+
+        Ok(buffer)
+    }
+
+    // $(cmd), what cmd writes to stdout without the trailing newlines.
+    fn capture(&mut self, source: &str) -> Result<String> {
+        let mut lexer = Lexer::new(source.chars().collect());
+        lexer.set_alias(self.state.alias.to_owned());
+
+        let node = Parser::new(lexer).parse()?;
+
+        let (mut read, write) = redirect::pipe()?;
+
+        let pid = self.fork_node(node, None, Some(write), RedirectTable::new())?;
+
+        let mut output = String::new();
+
+        let result = read.read_to_string(&mut output);
+
+        self.state.status = wait(pid)?;
+
+        if let Err(err) = result {
+            Err(Error::new(ErrorKind::ExecutionFailed, err.to_string()))?
+        }
+
+        Ok(output.trim_end_matches('\n').to_owned())
+    }
+}
+
+// from the open character, which is next, up to the matching close, both included.
+// none when the string ends before the close.
+fn enclosed(chars: &mut Peekable<Chars>, open: char, close: char) -> Option<String> {
+    let mut buffer = String::new();
+
+    let mut depth = 0;
+
+    // like the lexer, it does not count those in quotes.
+    let mut quote = None;
+
+    for ch in chars.by_ref() {
+        buffer.push(ch);
+
+        match quote {
+            Some(quote_ch) if ch == quote_ch => quote = None,
+            Some(_) => {}
+            None if ch == '\'' || ch == '"' => quote = Some(ch),
+            None if ch == open => depth += 1,
+            None if ch == close => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(buffer);
+                }
+            }
+            None => {}
+        }
+    }
+
+    None
+}
+
+impl arithmetic::Scope for Evaluator {
+    fn get(&self, key: &str) -> Option<String> {
+        self.lookup(key)
+    }
+
+    fn set(&mut self, key: &str, val: String) -> Result<()> {
+        self.state.variable.insert(key.to_owned(), val)
+    }
+}
+
+// runs the closure in a copy of the shell, with stdin and stdout replaced if given,
+// and the redirect table applied after them. the child exits with what the closure returns.
+fn fork(
+    stdin: Option<File>,
+    stdout: Option<File>,
+    redirect_table: RedirectTable,
+    run: impl FnOnce() -> i32,
+) -> Result<libc::pid_t> {
+    io::stdout().flush().ok();
+
+    let pid = unsafe { libc::fork() };
+
+    if pid < 0 {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            io::Error::last_os_error().to_string(),
+        ))?
+    }
+
+    if pid > 0 {
+        return Ok(pid);
+    }
+
+    for (fd, file) in [(0, stdin), (1, stdout)] {
+        if let Some(file) = file {
+            unsafe { libc::dup2(file.as_raw_fd(), fd) };
+        }
+    }
+
+    if let Err(err) = redirect_table.apply() {
+        io::stderr()
+            .lock()
+            .write_all(format!("{err}\n").as_bytes())
+            .ok();
+        process::exit(1)
+    }
+
+    drop(redirect_table);
+
+    redirect::close_high();
+
+    let code = run();
+
+    io::stdout().flush().ok();
+
+    process::exit(code)
+}
+
+fn stdio(file: Option<File>) -> process::Stdio {
+    match file {
+        Some(file) => process::Stdio::from(file),
+        None => process::Stdio::inherit(),
+    }
+}
+
+// waits for pid and returns its exit status, 128 + N when it was killed by signal N.
+fn wait(pid: libc::pid_t) -> Result<i32> {
+    let mut status = 0;
+
+    if unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+        Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            io::Error::last_os_error().to_string(),
+        ))?
+    }
+
+    if libc::WIFSIGNALED(status) {
+        return Ok(128 + libc::WTERMSIG(status));
+    }
+
+    Ok(libc::WEXITSTATUS(status))
 }
 
 fn get_path() -> Result<PathBuf> {
@@ -1,6 +1,10 @@
 use crate::ansi;
+use crate::builtin::jump;
+use std::collections::BTreeMap;
+use std::fs;
 use std::io;
 use std::io::{stdout, Write};
+use std::path::PathBuf;
 use std::process::exit;
 
 pub struct Terminal {
@@ -8,6 +12,16 @@ pub struct Terminal {
     buffer_index: usize,
     prompt: String,
     origin_termios: libc::termios,
+    // abbr, expanded when space is pressed after the first word of a command.
+    abbrs: BTreeMap<String, String>,
+    // the database of j, that tab completes the argument of cd from.
+    jump_path: Option<PathBuf>,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Terminal {
@@ -17,6 +31,8 @@ impl Terminal {
             buffer_index: 0,
             prompt: String::new(),
             origin_termios: termios(),
+            abbrs: BTreeMap::new(),
+            jump_path: None,
         }
     }
 
@@ -24,6 +40,14 @@ impl Terminal {
         self.prompt = prompt;
     }
 
+    pub fn set_abbrs(&mut self, abbrs: BTreeMap<String, String>) {
+        self.abbrs = abbrs;
+    }
+
+    pub fn set_jump_path(&mut self, path: Option<PathBuf>) {
+        self.jump_path = path;
+    }
+
     pub fn read_line(&mut self) -> io::Result<String> {
         self.set_raw_mode();
 
@@ -62,19 +86,16 @@ impl Terminal {
                             67 => {
                                 if self.buffer_index < self.buffer.len() {
                                     self.buffer_index += 1;
-                                    stdout.write_all(
-                                        format!("{}", ansi::Cursor::Right.get_esc_code())
-                                            .as_bytes(),
-                                    )?;
+                                    stdout
+                                        .write_all(ansi::Cursor::Right.get_esc_code().as_bytes())?;
                                 }
                             }
 
                             //left
                             68 => {
                                 if self.buffer_index > 0 {
-                                    stdout.write_all(
-                                        format!("{}", ansi::Cursor::Left.get_esc_code()).as_bytes(),
-                                    )?;
+                                    stdout
+                                        .write_all(ansi::Cursor::Left.get_esc_code().as_bytes())?;
                                     self.buffer_index -= 1;
                                 }
                             }
@@ -86,6 +107,47 @@ impl Terminal {
                         self.backspace()?;
                     }
 
+                    9 => {
+                        if !self.complete_dir() {
+                            continue;
+                        }
+
+                        stdout.write_all(ansi::Cursor::ClearLine.get_esc_code().as_bytes())?;
+
+                        stdout.write_all(
+                            format!("\r{}{}", self.prompt, String::from_utf8_lossy(&self.buffer))
+                                .as_bytes(),
+                        )?;
+
+                        if self.buffer_index < self.buffer.len() {
+                            let move_position = self.prompt.len() + self.buffer_index;
+
+                            stdout.write_all(
+                                ansi::Cursor::Move(move_position).get_esc_code().as_bytes(),
+                            )?;
+                        }
+                    }
+
+                    32 if self.expand_abbr() => {
+                        stdout.write_all(ansi::Cursor::ClearLine.get_esc_code().as_bytes())?;
+
+                        self.buffer.insert(self.buffer_index, char);
+                        self.buffer_index += 1;
+
+                        stdout.write_all(
+                            format!("\r{}{}", self.prompt, String::from_utf8_lossy(&self.buffer))
+                                .as_bytes(),
+                        )?;
+
+                        if self.buffer_index < self.buffer.len() {
+                            let move_position = self.prompt.len() + self.buffer_index;
+
+                            stdout.write_all(
+                                ansi::Cursor::Move(move_position).get_esc_code().as_bytes(),
+                            )?;
+                        }
+                    }
+
                     _ => {
                         self.buffer.insert(self.buffer_index, char);
 
@@ -93,10 +155,8 @@ impl Terminal {
 
                         for i in 0..self.buffer.len() {
                             if i != 0 {
-                                stdout.write_all(
-                                    format!("{}", ansi::Cursor::Backspace.get_esc_code())
-                                        .as_bytes(),
-                                )?;
+                                stdout
+                                    .write_all(ansi::Cursor::Backspace.get_esc_code().as_bytes())?;
                             }
                         }
 
@@ -109,8 +169,7 @@ impl Terminal {
                             let move_position = self.prompt.len() + self.buffer_index;
 
                             stdout.write_all(
-                                format!("{}", ansi::Cursor::Move(move_position).get_esc_code())
-                                    .as_bytes(),
+                                ansi::Cursor::Move(move_position).get_esc_code().as_bytes(),
                             )?;
                         }
                     }
@@ -120,18 +179,131 @@ impl Terminal {
 
         self.unset_raw_mode();
 
-        stdout.write(b"\n")?;
+        stdout.write_all(b"\n")?;
 
         let string = String::from_utf8_lossy(&self.buffer);
 
         Ok(string.to_string())
     }
 
+    // replaces the word before the cursor with its abbreviation, if it is the first word of a command.
+    fn expand_abbr(&mut self) -> bool {
+        let line = String::from_utf8_lossy(&self.buffer[..self.buffer_index]).to_string();
+
+        let is_separator = |ch: char| matches!(ch, ';' | '|' | '&' | '(');
+
+        let start = match line
+            .char_indices()
+            .rfind(|(_, ch)| ch.is_whitespace() || is_separator(*ch))
+        {
+            Some((i, ch)) => i + ch.len_utf8(),
+            None => 0,
+        };
+
+        if line[..start].trim_end().chars().last().is_some_and(|ch| !is_separator(ch)) {
+            return false;
+        }
+
+        let val = match self.abbrs.get(&line[start..]) {
+            Some(val) => val.to_owned(),
+            None => return false,
+        };
+
+        self.buffer.splice(start..self.buffer_index, val.bytes());
+        self.buffer_index = start + val.len();
+
+        true
+    }
+
+    // completes the argument of cd before the cursor: to the directories that start with it,
+    // as far as they agree, or else to the best directory of j that matches it.
+    fn complete_dir(&mut self) -> bool {
+        let line = String::from_utf8_lossy(&self.buffer[..self.buffer_index]).to_string();
+
+        let is_separator = |ch: char| matches!(ch, ';' | '|' | '&' | '(');
+
+        let start = match line.char_indices().rfind(|(_, ch)| ch.is_whitespace()) {
+            Some((i, ch)) => i + ch.len_utf8(),
+            None => return false,
+        };
+
+        let command = line[..start]
+            .trim_end()
+            .rsplit(is_separator)
+            .next()
+            .unwrap_or_default()
+            .trim();
+
+        if !matches!(command, "cd" | "pushd") {
+            return false;
+        }
+
+        let word = &line[start..];
+
+        let (parent, prefix) = match word.rfind('/') {
+            Some(i) => (&word[..=i], &word[i + 1..]),
+            None => ("", word),
+        };
+
+        let mut names = fs::read_dir(match parent.is_empty() {
+            true => ".",
+            false => parent,
+        })
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with(prefix))
+                .filter(|name| !name.starts_with('.') || prefix.starts_with('.'))
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+        names.sort();
+
+        let completion = match names.as_slice() {
+            // the database is only read when nothing here completes the word.
+            [] => match self
+                .jump_path
+                .as_ref()
+                .filter(|_| !word.is_empty())
+                .and_then(|path| jump::best(path, word))
+            {
+                Some(dir) => format!("{dir}/"),
+                None => return false,
+            },
+            [name] => format!("{parent}{name}/"),
+            [first, ..] => {
+                let common = names.iter().fold(first.to_owned(), |common, name| {
+                    common
+                        .chars()
+                        .zip(name.chars())
+                        .take_while(|(left, right)| left == right)
+                        .map(|(ch, _)| ch)
+                        .collect()
+                });
+
+                format!("{parent}{common}")
+            }
+        };
+
+        if completion == word {
+            return false;
+        }
+
+        self.buffer
+            .splice(start..self.buffer_index, completion.bytes());
+        self.buffer_index = start + completion.len();
+
+        true
+    }
+
     fn backspace(&mut self) -> io::Result<()> {
         let stdout = stdout();
         let mut stdout = stdout.lock();
 
-        if self.buffer_index <= 0 {
+        if self.buffer_index == 0 {
             return Ok(());
         }
 
@@ -139,8 +311,7 @@ impl Terminal {
 
         for i in 0..self.buffer.len() {
             if i != 0 {
-                stdout
-                    .write_all(format!("{}", ansi::Cursor::Backspace.get_esc_code()).as_bytes())?;
+                stdout.write_all(ansi::Cursor::Backspace.get_esc_code().as_bytes())?;
             }
         }
 
@@ -150,21 +321,14 @@ impl Terminal {
 
         self.buffer.remove(self.buffer_index);
 
-        stdout.write_all(format!("{}", ansi::Cursor::Backspace.get_esc_code()).as_bytes())?;
+        stdout.write_all(ansi::Cursor::Backspace.get_esc_code().as_bytes())?;
         stdout.write_all(
-            format!(
-                "\r{}{}",
-                self.prompt,
-                String::from_utf8_lossy(&self.buffer).to_string()
-            )
-            .as_bytes(),
+            format!("\r{}{}", self.prompt, String::from_utf8_lossy(&self.buffer)).as_bytes(),
         )?;
 
         if self.buffer_index < self.buffer.len() {
             let move_position = self.prompt.len() + self.buffer_index - 1;
-            stdout.write_all(
-                format!("{}", ansi::Cursor::Move(move_position).get_esc_code()).as_bytes(),
-            )?;
+            stdout.write_all(ansi::Cursor::Move(move_position).get_esc_code().as_bytes())?;
         }
 
         Ok(())
@@ -198,8 +362,8 @@ impl Terminal {
 
         let mut raw = self.origin_termios;
 
-        raw.c_lflag = raw.c_lflag & !(libc::ICANON | libc::ECHO | libc::IEXTEN | libc::ISIG);
-        // raw.c_lflag = raw.c_lflag & !(libc::ICANON | libc::ECHO );
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::IEXTEN | libc::ISIG);
+        // raw.c_lflag &= !(libc::ICANON | libc::ECHO );
         raw.c_cc[libc::VTIME] = 0;
 
         raw.c_cc[libc::VMIN] = 1;
@@ -217,6 +381,40 @@ impl Terminal {
     }
 }
 
+// the terminal on stdin with some of its local modes turned off, e.g. ECHO for read -s,
+// which are put back when it is dropped. nothing when stdin is not a terminal.
+pub struct Mode {
+    origin_termios: libc::termios,
+}
+
+impl Mode {
+    pub fn unset(flags: libc::tcflag_t) -> Option<Self> {
+        if unsafe { libc::isatty(0) } != 1 {
+            return None;
+        }
+
+        let mut origin_termios = termios();
+
+        unsafe { libc::tcgetattr(0, &mut origin_termios) };
+
+        let mut raw = origin_termios;
+
+        raw.c_lflag &= !flags;
+        raw.c_cc[libc::VTIME] = 0;
+        raw.c_cc[libc::VMIN] = 1;
+
+        unsafe { libc::tcsetattr(0, 0, &raw) };
+
+        Some(Self { origin_termios })
+    }
+}
+
+impl Drop for Mode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(0, 0, &self.origin_termios) };
+    }
+}
+
 fn getch() -> Option<u8> {
     let code = [0; 1];
 
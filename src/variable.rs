@@ -1,45 +1,575 @@
+use crate::error::*;
+use std::collections::BTreeMap;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::env;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
-pub struct Variable(HashMap<String, String>);
+// the value of a variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    // =s abc
+    String(String),
+    // =xs (a b c)
+    Array(Vec<String>),
+    // =m ([key]=val [key2]=val2)
+    Map(BTreeMap<String, String>),
+    // =t (a, b, c), fixed once created
+    Tuple(Vec<String>),
+}
+
+impl Value {
+    // the words that $NAME expands to, every element is a word of its own.
+    pub fn to_words(&self) -> Vec<String> {
+        match self {
+            Value::String(string) => vec![string.to_owned()],
+            Value::Array(vals) | Value::Tuple(vals) => vals.to_owned(),
+            Value::Map(map) => map.values().cloned().collect(),
+        }
+    }
+
+    // $!NAME, the indexes of an array or tuple and the keys of a map.
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            Value::String(string) => (0..string.chars().count()).map(|i| i.to_string()).collect(),
+            Value::Array(vals) | Value::Tuple(vals) => {
+                (0..vals.len()).map(|i| i.to_string()).collect()
+            }
+            Value::Map(map) => map.keys().cloned().collect(),
+        }
+    }
+
+    // $#NAME, the number of characters of a string and of elements otherwise.
+    pub fn len(&self) -> usize {
+        match self {
+            Value::String(string) => string.chars().count(),
+            Value::Array(vals) | Value::Tuple(vals) => vals.len(),
+            Value::Map(map) => map.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // $NAME[I], a negative index counts from the end.
+    pub fn get(&self, index: &str) -> Result<Option<String>> {
+        match self {
+            Value::String(string) => {
+                let chars = string.chars().collect::<Vec<char>>();
+
+                Ok(position(index, chars.len())?.map(|i| chars[i].to_string()))
+            }
+            Value::Array(vals) | Value::Tuple(vals) => {
+                Ok(position(index, vals.len())?.map(|i| vals[i].to_owned()))
+            }
+            Value::Map(map) => Ok(map.get(index).cloned()),
+        }
+    }
+
+    // $NAME[I:J], from I up to but not including J, either of which can be left out.
+    pub fn slice(&self, start: &str, end: &str) -> Result<Vec<String>> {
+        let range = |len: usize| -> Result<(usize, usize)> {
+            let start = bound(start, len, 0)?;
+            let end = bound(end, len, len)?;
+
+            Ok((start, end.max(start)))
+        };
+
+        match self {
+            Value::String(string) => {
+                let chars = string.chars().collect::<Vec<char>>();
+                let (start, end) = range(chars.len())?;
+
+                Ok(vec![chars[start..end].iter().collect()])
+            }
+            Value::Array(vals) | Value::Tuple(vals) => {
+                let (start, end) = range(vals.len())?;
+
+                Ok(vals[start..end].to_vec())
+            }
+            Value::Map(_) => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                "a map cannot be sliced".to_owned(),
+            )),
+        }
+    }
+
+    // =NAME[I] val
+    pub fn set(&mut self, index: &str, val: String) -> Result<()> {
+        match self {
+            Value::Array(vals) => {
+                // assigning one past the end appends.
+                if index.parse::<usize>().ok() == Some(vals.len()) {
+                    vals.push(val);
+                    return Ok(());
+                }
+
+                match position(index, vals.len())? {
+                    Some(i) => vals[i] = val,
+                    None => Err(Error::new(
+                        ErrorKind::ExecutionFailed,
+                        format!("index out of range: {index}"),
+                    ))?,
+                }
+            }
+            Value::Map(map) => {
+                map.insert(index.to_owned(), val);
+            }
+            Value::String(_) => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                "an element cannot be set on a string".to_owned(),
+            ))?,
+            Value::Tuple(_) => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                "a tuple cannot be changed".to_owned(),
+            ))?,
+        }
+
+        Ok(())
+    }
+}
+
+fn index(index: &str) -> Result<isize> {
+    match index.trim().parse::<isize>() {
+        Ok(i) => Ok(i),
+        Err(_) => Err(Error::new(
+            ErrorKind::ExecutionFailed,
+            format!("not an index: {index}"),
+        )),
+    }
+}
+
+// the element that an index points to, None when it is out of range.
+fn position(i: &str, len: usize) -> Result<Option<usize>> {
+    let i = index(i)?;
+
+    let i = match i < 0 {
+        true => len as isize + i,
+        false => i,
+    };
+
+    match 0 <= i && i < len as isize {
+        true => Ok(Some(i as usize)),
+        false => Ok(None),
+    }
+}
+
+// a bound of a slice, clamped to 0 ~ len.
+fn bound(i: &str, len: usize, default: usize) -> Result<usize> {
+    if i.trim().is_empty() {
+        return Ok(default);
+    }
+
+    let i = index(i)?;
+
+    let i = match i < 0 {
+        true => len as isize + i,
+        false => i,
+    };
+
+    Ok(i.clamp(0, len as isize) as usize)
+}
+
+// the attributes that export, readonly and declare give a variable.
+#[derive(Debug, Clone, Default)]
+pub struct Attributes {
+    // passed on to the environment of commands
+    pub is_exported: bool,
+    // cannot be assigned or unset
+    pub is_readonly: bool,
+    // an assignment is evaluated as an arithmetic expression
+    pub is_integer: bool,
+    // belongs to the scope of a function or block
+    pub is_local: bool,
+}
+
+// a variable, which can have attributes before it has a value, e.g. export NAME
+#[derive(Debug, Clone)]
+pub struct Entry {
+    value: Option<Value>,
+    attributes: Attributes,
+}
+
+pub struct Variable {
+    entries: HashMap<String, Entry>,
+    // the frames of blocks and sourced files, innermost last. a lookup walks them outward.
+    scopes: Vec<HashMap<String, Entry>>,
+    // $$ and $PPID, which stay those of the shell in subshells
+    pid: u32,
+    ppid: i32,
+    // $0, the name of the shell or of the script
+    name: String,
+    // $!, the last command started in the background
+    background: Option<i32>,
+    // $LINENO, the line of the command being run
+    line: usize,
+    // $1 ~ $9, $# and $@, the arguments of the sourced file
+    args: Vec<String>,
+    // $SECONDS counts from here.
+    started: Instant,
+    // the state of $RANDOM
+    seed: Cell<u64>,
+}
 
 impl ToOwned for Variable {
     type Owned = Variable;
 
     fn to_owned(&self) -> Self::Owned {
         Self {
-            0: self.0.to_owned(),
+            entries: self.entries.to_owned(),
+            scopes: self.scopes.to_owned(),
+            pid: self.pid,
+            ppid: self.ppid,
+            name: self.name.to_owned(),
+            background: self.background,
+            line: self.line,
+            args: self.args.to_owned(),
+            started: self.started,
+            seed: self.seed.to_owned(),
         }
     }
 }
 
+impl Default for Variable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Variable {
     pub fn new() -> Self {
-        Self { 0: HashMap::new() }
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self {
+            entries: HashMap::new(),
+            scopes: Vec::new(),
+            pid: process::id(),
+            ppid: unsafe { libc::getppid() },
+            name: env::args().next().unwrap_or("nsh".to_owned()),
+            background: None,
+            line: 0,
+            args: Vec::new(),
+            started: Instant::now(),
+            // xorshift gets stuck at 0.
+            seed: Cell::new((nanos ^ process::id() as u64) | 1),
+        }
     }
 
-    pub fn remove(&mut self,key:String){
-        self.0.remove(&key);
+    // every variable of the environment, exported.
+    pub fn from_environ() -> Self {
+        let mut variable = Self::new();
+
+        for (key, val) in env::vars_os() {
+            variable.entries.insert(
+                key.to_string_lossy().to_string(),
+                Entry {
+                    value: Some(Value::String(val.to_string_lossy().to_string())),
+                    attributes: Attributes {
+                        is_exported: true,
+                        ..Default::default()
+                    },
+                },
+            );
+        }
+
+        if variable.get_value("PWD").is_none() {
+            if let Ok(dir) = env::current_dir() {
+                variable.insert("PWD".to_owned(), dir.to_string_lossy().to_string()).ok();
+                variable.attributes_mut("PWD").is_exported = true;
+            }
+        }
+
+        variable
     }
 
-    pub fn append(&mut self, key: String, val: String) {
-        match self.0.get_mut(&key) {
-            Some(string) => {
-                string.push(':');
-                string.push_str(&val)
+    // the variables that are computed when they are looked up, e.g. $$ and $RANDOM
+    pub fn dynamic(&self, key: &str) -> Option<Value> {
+        let string = match key {
+            "$" => self.pid.to_string(),
+            "!" => self.background?.to_string(),
+            "0" => self.name.to_owned(),
+            "PPID" => self.ppid.to_string(),
+            "LINENO" => self.line.to_string(),
+            "#" => self.args.len().to_string(),
+            "@" | "*" => return Some(Value::Array(self.args.to_owned())),
+            "SECONDS" => self.started.elapsed().as_secs().to_string(),
+            "RANDOM" => (self.random() % 32768).to_string(),
+            "EPOCHSECONDS" => epoch().as_secs().to_string(),
+            "EPOCHREALTIME" => {
+                let epoch = epoch();
+                format!("{}.{:06}", epoch.as_secs(), epoch.subsec_micros())
             }
+            // $1 ~ $9
+            _ => match key.parse::<usize>() {
+                Ok(n) if n > 0 => self.args.get(n - 1)?.to_owned(),
+                _ => return None,
+            },
+        };
+
+        Some(Value::String(string))
+    }
+
+    // xorshift64*, good enough for $RANDOM.
+    fn random(&self) -> u64 {
+        let mut x = self.seed.get();
+
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+
+        self.seed.set(x);
+
+        x.wrapping_mul(0x2545F4914F6CDD1D) >> 32
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name = name
+    }
+
+    pub fn set_background(&mut self, pid: i32) {
+        self.background = Some(pid)
+    }
+
+    pub fn set_line(&mut self, line: usize) {
+        self.line = line
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    // a frame, whose local variables shadow the outer ones until it is popped.
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new())
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    // local NAME, the variable is declared in the innermost frame without a value.
+    pub fn local(&mut self, key: &str) -> Result<()> {
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                "local: can only be used in a block or a sourced file".to_owned(),
+            ))?,
+        };
+
+        scope.entry(key.to_owned()).or_insert(Entry {
+            value: None,
+            attributes: Attributes {
+                is_local: true,
+                ..Default::default()
+            },
+        });
+
+        Ok(())
+    }
+
+    // the variable from the innermost frame that has it.
+    fn find(&self, key: &str) -> Option<&Entry> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(key))
+            .or_else(|| self.entries.get(key))
+    }
+
+    // the innermost frame that has the variable, where it is assigned. a new variable is global.
+    fn frame_mut(&mut self, key: &str) -> &mut HashMap<String, Entry> {
+        match self.scopes.iter().rposition(|scope| scope.contains_key(key)) {
+            Some(i) => &mut self.scopes[i],
+            None => &mut self.entries,
+        }
+    }
+
+    pub fn remove(&mut self, key: String) -> Result<()> {
+        self.check(&key)?;
+
+        self.frame_mut(&key).remove(&key);
+
+        Ok(())
+    }
+
+    // pushes val onto the array, a string becomes the first element.
+    pub fn append(&mut self, key: String, val: String) -> Result<()> {
+        self.check(&key)?;
+
+        match self.frame_mut(&key).get_mut(&key).and_then(|entry| entry.value.as_mut()) {
+            Some(Value::Array(vals)) => vals.push(val),
+            Some(value) => {
+                let mut vals = value.to_words();
+                vals.push(val);
+                *value = Value::Array(vals);
+            }
+            None => self.insert_value(key, Value::Array(vec![val]))?,
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, key: String, val: String) -> Result<()> {
+        self.insert_value(key, Value::String(val))
+    }
+
+    // keeps the attributes of the variable.
+    pub fn insert_value(&mut self, key: String, val: Value) -> Result<()> {
+        self.check(&key)?;
+
+        // =SECONDS n counts on from n, and =RANDOM n seeds the numbers.
+        if let Value::String(string) = &val {
+            match (key.as_str(), string.trim().parse::<u64>()) {
+                ("SECONDS", Ok(n)) => {
+                    self.started = Instant::now().checked_sub(Duration::from_secs(n)).unwrap_or(self.started);
+                    return Ok(());
+                }
+                ("RANDOM", Ok(n)) => {
+                    self.seed.set(n | 1);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        let frame = self.frame_mut(&key);
+
+        match frame.get_mut(&key) {
+            Some(entry) => entry.value = Some(val),
             None => {
-                self.0.insert(key, val);
+                frame.insert(
+                    key,
+                    Entry {
+                        value: Some(val),
+                        attributes: Attributes::default(),
+                    },
+                );
             }
         }
+
+        Ok(())
     }
 
-    pub fn insert(&mut self, key: String, val: String) {
-        self.0.insert(key, val);
+    // =NAME[I] val, an unset variable becomes an array for a number and a map otherwise.
+    pub fn insert_element(&mut self, key: String, index: &str, val: String) -> Result<()> {
+        self.check(&key)?;
+
+        match self.frame_mut(&key).get_mut(&key).and_then(|entry| entry.value.as_mut()) {
+            Some(value) => value.set(index, val),
+            None => {
+                let mut value = match index.parse::<usize>() {
+                    Ok(_) => Value::Array(Vec::new()),
+                    Err(_) => Value::Map(BTreeMap::new()),
+                };
+
+                value.set(index, val)?;
+
+                self.insert_value(key, value)
+            }
+        }
     }
 
-    //pub fn get(&self,key:String)->Option<&String>{
-    //    self.0.get(&key)
+    // only a string, the other values are reached with get_value.
     pub fn get(&self, key: String) -> Option<&str> {
-        self.0.get(&key).map(|x| &**x)
+        match self.get_value(&key) {
+            Some(Value::String(string)) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn get_value(&self, key: &str) -> Option<&Value> {
+        self.find(key).and_then(|entry| entry.value.as_ref())
+    }
+
+    pub fn attributes(&self, key: &str) -> Attributes {
+        match self.find(key) {
+            Some(entry) => entry.attributes.to_owned(),
+            None => Attributes::default(),
+        }
+    }
+
+    // the variable is created without a value if it does not exist.
+    pub fn attributes_mut(&mut self, key: &str) -> &mut Attributes {
+        &mut self
+            .frame_mut(key)
+            .entry(key.to_owned())
+            .or_insert(Entry {
+                value: None,
+                attributes: Attributes::default(),
+            })
+            .attributes
     }
+
+    // the names of every variable, sorted.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = self.entries.keys().cloned().collect::<Vec<String>>();
+
+        for scope in self.scopes.iter() {
+            keys.extend(scope.keys().cloned());
+        }
+
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    // the environment of a command, every exported variable that has a value.
+    pub fn environ(&self) -> Vec<(String, String)> {
+        self.keys()
+            .into_iter()
+            .filter_map(|key| {
+                let entry = self.find(&key).filter(|entry| entry.attributes.is_exported)?;
+                let val = entry.value.as_ref()?.to_words().join(" ");
+                Some((key, val))
+            })
+            .collect()
+    }
+
+    // a copy of the variable, to be put back with restore, e.g. around NAME=val cmd
+    pub fn entry(&self, key: &str) -> Option<Entry> {
+        self.find(key).cloned()
+    }
+
+    pub fn restore(&mut self, key: String, entry: Option<Entry>) {
+        let frame = self.frame_mut(&key);
+
+        match entry {
+            Some(entry) => frame.insert(key, entry),
+            None => frame.remove(&key),
+        };
+    }
+
+    fn check(&self, key: &str) -> Result<()> {
+        if self.attributes(key).is_readonly {
+            Err(Error::new(
+                ErrorKind::ExecutionFailed,
+                format!("{key}: readonly variable"),
+            ))?
+        }
+
+        Ok(())
+    }
+}
+
+fn epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
 }
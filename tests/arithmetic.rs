@@ -0,0 +1,121 @@
+use nsh::arithmetic;
+use nsh::arithmetic::Scope;
+use nsh::error::*;
+use std::collections::BTreeMap;
+
+// the variables of the expressions, without a shell.
+#[derive(Default)]
+struct Variables(BTreeMap<String, String>);
+
+impl Scope for Variables {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, val: String) -> Result<()> {
+        self.0.insert(key.to_owned(), val);
+        Ok(())
+    }
+}
+
+fn eval(source: &str) -> Result<i64> {
+    arithmetic::eval(source, &mut Variables::default())
+}
+
+#[test]
+fn precedence() {
+    assert_eq!(eval("1 + 2 * 3").unwrap(), 7);
+    assert_eq!(eval("(1 + 2) * 3").unwrap(), 9);
+    assert_eq!(eval("2 ** 3 ** 2").unwrap(), 512);
+    assert_eq!(eval("-2 ** 2").unwrap(), 4);
+    assert_eq!(eval("1 << 2 + 1").unwrap(), 8);
+    assert_eq!(eval("1 | 2 ^ 3 & 1").unwrap(), 3);
+    assert_eq!(eval("1 < 2 == 1").unwrap(), 1);
+    assert_eq!(eval("0 || 1 && 0").unwrap(), 0);
+    assert_eq!(eval("7 / 2 % 2").unwrap(), 1);
+    assert_eq!(eval("1 ? 2 : 0 ? 3 : 4").unwrap(), 2);
+}
+
+#[test]
+fn assignment_operators() {
+    let mut variables = Variables::default();
+
+    assert_eq!(arithmetic::eval("a = 5", &mut variables).unwrap(), 5);
+    assert_eq!(arithmetic::eval("a += 3", &mut variables).unwrap(), 8);
+    assert_eq!(arithmetic::eval("a -= 2", &mut variables).unwrap(), 6);
+    assert_eq!(arithmetic::eval("a *= 4", &mut variables).unwrap(), 24);
+    assert_eq!(arithmetic::eval("a /= 5", &mut variables).unwrap(), 4);
+    assert_eq!(arithmetic::eval("a %= 3", &mut variables).unwrap(), 1);
+    assert_eq!(arithmetic::eval("a <<= 3", &mut variables).unwrap(), 8);
+    assert_eq!(arithmetic::eval("a |= 3", &mut variables).unwrap(), 11);
+    assert_eq!(arithmetic::eval("a++ + ++a", &mut variables).unwrap(), 24);
+    assert_eq!(
+        arithmetic::eval("b = a = 2, a + b", &mut variables).unwrap(),
+        4
+    );
+    assert_eq!(variables.get("a"), Some("2".to_owned()));
+}
+
+#[test]
+fn variables_hold_expressions() {
+    let mut variables = Variables::default();
+    variables.set("a", "b + 1".to_owned()).unwrap();
+    variables.set("b", "2".to_owned()).unwrap();
+
+    assert_eq!(arithmetic::eval("a * unset", &mut variables).unwrap(), 0);
+    assert_eq!(arithmetic::eval("a * 2", &mut variables).unwrap(), 6);
+}
+
+#[test]
+fn short_circuit() {
+    let mut variables = Variables::default();
+
+    assert_eq!(arithmetic::eval("0 && (a = 1)", &mut variables).unwrap(), 0);
+    assert_eq!(arithmetic::eval("1 || (a = 1)", &mut variables).unwrap(), 1);
+    assert_eq!(
+        arithmetic::eval("1 ? 2 : (a = 1)", &mut variables).unwrap(),
+        2
+    );
+    assert_eq!(variables.get("a"), None);
+
+    assert_eq!(eval("0 && 1 / 0").unwrap(), 0);
+    assert_eq!(eval("0 ? 1 / 0 : 3").unwrap(), 3);
+}
+
+#[test]
+fn division_by_zero() {
+    for source in ["1 / 0", "1 % 0", "a /= 0"] {
+        assert!(matches!(
+            eval(source).unwrap_err().kind(),
+            ErrorKind::DivisionByZero
+        ));
+    }
+}
+
+#[test]
+fn overflow() {
+    for source in [
+        "9223372036854775807 + 1",
+        "-9223372036854775807 - 2",
+        "4611686018427387904 * 2",
+        "(-9223372036854775807 - 1) / -1",
+        "1 << 70",
+        "2 ** 63",
+        "99999999999999999999",
+    ] {
+        assert!(
+            matches!(eval(source).unwrap_err().kind(), ErrorKind::Overflow),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn wrong_syntax() {
+    for source in ["2 ** -1", "1 +", "(1", "1 2", "a = = 1", "1 @ 2"] {
+        assert!(
+            matches!(eval(source).unwrap_err().kind(), ErrorKind::WrongSyntax),
+            "{source}"
+        );
+    }
+}
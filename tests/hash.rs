@@ -0,0 +1,199 @@
+use nsh::builtin::hash;
+use nsh::builtin::Builtin;
+use nsh::shell::ShellState;
+use std::env;
+use std::fs;
+use std::io;
+
+fn hex(name: &str, input: &[u8]) -> String {
+    hash::hex(&hash::digest(name, &mut io::Cursor::new(input)).unwrap())
+}
+
+// runs the builtin with the input as stdin, and returns its status, stdout and stderr.
+fn run(name: &'static str, args: &[&str], input: &[u8]) -> (i32, String, String) {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+
+    let status = hash::Hash { name }.run(
+        args.iter().map(|arg| arg.to_string()).collect(),
+        &mut io::Cursor::new(input),
+        &mut stdout,
+        &mut stderr,
+        &mut ShellState::default(),
+    );
+
+    (
+        status,
+        String::from_utf8(stdout).unwrap(),
+        String::from_utf8(stderr).unwrap(),
+    )
+}
+
+// a file of its own for each test, as tests run in parallel.
+fn temp_file(name: &str, contents: &str) -> String {
+    let path = env::temp_dir().join(format!("nsh-test-hash-{}-{name}", std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn md5_vectors() {
+    assert_eq!(hex("md5", b""), "d41d8cd98f00b204e9800998ecf8427e");
+    assert_eq!(hex("md5", b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+}
+
+#[test]
+fn sha1_vectors() {
+    assert_eq!(hex("sha1", b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(hex("sha1", b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+}
+
+#[test]
+fn sha2_vectors() {
+    assert_eq!(
+        hex("sha2-224", b"abc"),
+        "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7"
+    );
+    assert_eq!(
+        hex("sha2-256", b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        hex("sha2-256", b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(hex("sha2", b"abc"), hex("sha2-256", b"abc"));
+    assert_eq!(
+        hex("sha2-384", b"abc"),
+        "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"
+    );
+    assert_eq!(
+        hex("sha2-512", b"abc"),
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+    );
+}
+
+#[test]
+fn sha3_vectors() {
+    assert_eq!(
+        hex("sha3-224", b"abc"),
+        "e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf"
+    );
+    assert_eq!(
+        hex("sha3-256", b""),
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+    );
+    assert_eq!(
+        hex("sha3-256", b"abc"),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+    );
+    assert_eq!(hex("sha3", b"abc"), hex("sha3-256", b"abc"));
+    assert_eq!(
+        hex("sha3-384", b"abc"),
+        "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b298d88cea927ac7f539f1edf228376d25"
+    );
+    assert_eq!(
+        hex("sha3-512", b"abc"),
+        "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0"
+    );
+}
+
+#[test]
+fn longer_than_a_chunk() {
+    let input = vec![b'a'; 1_000_000];
+
+    assert_eq!(
+        hex("sha2-256", &input),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+    assert_eq!(
+        hex("sha3-256", &input),
+        "5c8875ae474a3634ba4fd55ec85bffd661f32aca75c6d699d0cdcb6c115891c1"
+    );
+}
+
+#[test]
+fn prints_stdin_in_hex_and_base64() {
+    assert_eq!(
+        run("sha2-256", &[], b"abc"),
+        (
+            0,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  -\n".to_owned(),
+            String::new()
+        )
+    );
+    assert_eq!(
+        run("sha2-256", &["--base64", "-"], b"abc").1,
+        "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=  -\n"
+    );
+}
+
+#[test]
+fn prints_files() {
+    let path = temp_file("prints", "abc");
+
+    assert_eq!(
+        run("md5", &[&path], b"").1,
+        format!("900150983cd24fb0d6963f7d28e17f72  {path}\n")
+    );
+
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn prints_the_files_after_one_that_is_missing() {
+    let first = temp_file("first", "abc");
+    let last = temp_file("last", "abc");
+    let missing = format!("{first}-missing");
+
+    let (status, stdout, stderr) = run("md5", &[&first, &missing, &last], b"");
+
+    assert_eq!(status, 1);
+    assert_eq!(
+        stdout,
+        format!(
+            "900150983cd24fb0d6963f7d28e17f72  {first}\n900150983cd24fb0d6963f7d28e17f72  {last}\n"
+        )
+    );
+    assert!(stderr.starts_with(&format!("md5: {missing}: ")));
+
+    fs::remove_file(first).ok();
+    fs::remove_file(last).ok();
+}
+
+#[test]
+fn check_reports_ok_and_failed() {
+    let good = temp_file("good", "abc");
+    let bad = temp_file("bad", "abd");
+
+    let list = format!(
+        "a9993e364706816aba3e25717850c26c9cd0d89d  {good}\na9993e364706816aba3e25717850c26c9cd0d89d *{bad}\n"
+    );
+
+    let (status, stdout, stderr) = run("sha1", &["--check"], list.as_bytes());
+
+    assert_eq!(status, 1);
+    assert_eq!(stdout, format!("{good}: OK\n{bad}: FAILED\n"));
+    assert_eq!(
+        stderr,
+        "sha1: WARNING: 1 computed checksum did NOT match\n"
+    );
+
+    let (status, stdout, _) = run("sha1", &["-c"], list.lines().next().unwrap().as_bytes());
+
+    assert_eq!((status, stdout), (0, format!("{good}: OK\n")));
+
+    fs::remove_file(good).ok();
+    fs::remove_file(bad).ok();
+}
+
+#[test]
+fn check_accepts_its_own_output() {
+    let path = temp_file("own", "nsh");
+
+    let (_, list, _) = run("sha3-512", &[&path], b"");
+    let (status, stdout, _) = run("sha3-512", &["--check"], list.as_bytes());
+
+    assert_eq!((status, stdout), (0, format!("{path}: OK\n")));
+
+    fs::remove_file(path).ok();
+}
@@ -0,0 +1,108 @@
+use nsh::builtin::printf::Printf;
+use nsh::builtin::Builtin;
+use nsh::shell::ShellState;
+use nsh::variable::Value;
+use std::io;
+
+// runs the builtin in the state, and returns its status, stdout and stderr.
+fn run_in(state: &mut ShellState, args: &[&str]) -> (i32, String, String) {
+    let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+
+    let status = Printf.run(
+        args.iter().map(|arg| arg.to_string()).collect(),
+        &mut io::empty(),
+        &mut stdout,
+        &mut stderr,
+        state,
+    );
+
+    (
+        status,
+        String::from_utf8(stdout).unwrap(),
+        String::from_utf8(stderr).unwrap(),
+    )
+}
+
+fn run(args: &[&str]) -> (i32, String, String) {
+    run_in(&mut ShellState::default(), args)
+}
+
+fn output(args: &[&str]) -> String {
+    let (status, stdout, stderr) = run(args);
+
+    assert_eq!((status, stderr.as_str()), (0, ""), "{args:?}");
+
+    stdout
+}
+
+#[test]
+fn directives_and_flags() {
+    assert_eq!(output(&["%s-%5s-%-5s|", "a", "b", "c"]), "a-    b-c    |");
+    assert_eq!(
+        output(&[
+            "%d %05d %+d %x %X %o %#x",
+            "7",
+            "42",
+            "3",
+            "255",
+            "255",
+            "8",
+            "255"
+        ]),
+        "7 00042 +3 ff FF 10 0xff"
+    );
+    assert_eq!(output(&["%.2f %c %%", "3.14159", "xyz"]), "3.14 x %");
+}
+
+#[test]
+fn quoted() {
+    assert_eq!(output(&["%q\n", "plain/path.txt"]), "plain/path.txt\n");
+    assert_eq!(output(&["%q\n", "a b"]), "'a b'\n");
+    assert_eq!(output(&["%q\n", "it's"]), "'it'\\''s'\n");
+    assert_eq!(output(&["%q\n", ""]), "''\n");
+}
+
+#[test]
+fn escapes_of_b() {
+    assert_eq!(output(&["%b|", "a\\tb\\n"]), "a\tb\n|");
+    assert_eq!(output(&["%b", "\\0101\\101"]), "AA");
+    assert_eq!(output(&["%s", "a\\tb"]), "a\\tb");
+    assert_eq!(output(&["%b%s", "stop\\cignored", "never"]), "stop");
+}
+
+#[test]
+fn width_and_precision_from_arguments() {
+    assert_eq!(output(&["%*d|", "5", "42"]), "   42|");
+    assert_eq!(output(&["%-*d|", "4", "7"]), "7   |");
+    assert_eq!(output(&["%.*f|", "1", "2.25"]), "2.2|");
+}
+
+#[test]
+fn format_is_reused() {
+    assert_eq!(
+        output(&["%s=%s\n", "a", "1", "b", "2", "c"]),
+        "a=1\nb=2\nc=\n"
+    );
+    assert_eq!(output(&["[%d]", "1", "2", "3"]), "[1][2][3]");
+    assert_eq!(output(&["no directives\n", "a", "b"]), "no directives\n");
+}
+
+#[test]
+fn not_a_number_is_printed_as_zero() {
+    let (status, stdout, stderr) = run(&["%d %d", "1", "x"]);
+
+    assert_eq!((status, stdout.as_str()), (1, "1 0"));
+    assert!(stderr.starts_with("printf: "));
+}
+
+#[test]
+fn assigns_with_v() {
+    let mut state = ShellState::default();
+
+    let (status, stdout, _) = run_in(&mut state, &["-v", "V", "%s-%03d", "a", "7"]);
+
+    assert_eq!((status, stdout.as_str()), (0, ""));
+    assert_eq!(state.value("V"), Some(Value::String("a-007".to_owned())));
+
+    assert_eq!(run(&["-v"]).0, 1);
+}
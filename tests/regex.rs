@@ -0,0 +1,124 @@
+use nsh::parser::lexer::Lexer;
+use nsh::parser::token::Token;
+use nsh::pattern;
+use nsh::shell;
+use nsh::shell::ShellState;
+use nsh::variable::Value;
+
+fn captures(pattern: &str, text: &str) -> Option<Vec<String>> {
+    pattern::captures(pattern, text).unwrap()
+}
+
+#[test]
+fn captures_whole_match_and_groups() {
+    assert_eq!(
+        captures(r"^([a-z]+)-([0-9]+)\.([0-9]+)$", "release-1.24"),
+        Some(vec![
+            "release-1.24".to_owned(),
+            "release".to_owned(),
+            "1".to_owned(),
+            "24".to_owned(),
+        ])
+    );
+}
+
+#[test]
+fn captures_unmatched_group_as_empty() {
+    assert_eq!(
+        captures("(a)|(b)", "b"),
+        Some(vec!["b".to_owned(), "".to_owned(), "b".to_owned()])
+    );
+}
+
+#[test]
+fn captures_nothing_without_match() {
+    assert_eq!(captures("^x", "release"), None);
+}
+
+#[test]
+fn captures_first_match_only() {
+    assert_eq!(
+        captures("([0-9])", "a1b2"),
+        Some(vec!["1".to_owned(), "1".to_owned()])
+    );
+}
+
+#[test]
+fn captures_reports_invalid_regex() {
+    assert!(pattern::captures("(", "a").is_err());
+}
+
+#[test]
+fn replace_first_and_all() {
+    assert_eq!(
+        pattern::replace("a1b22", "[0-9]+", "N", false).unwrap(),
+        "aNb22"
+    );
+    assert_eq!(
+        pattern::replace("a1b22", "[0-9]+", "N", true).unwrap(),
+        "aNbN"
+    );
+}
+
+#[test]
+fn replace_with_groups() {
+    assert_eq!(
+        pattern::replace("release-1.24", "([a-z]+)-(.*)", "$2-$1", false).unwrap(),
+        "1.24-release"
+    );
+}
+
+#[test]
+fn parse_substitution() {
+    assert_eq!(
+        pattern::parse_substitution("S//a\\/b/c"),
+        Some(("S", "a/b".to_owned(), "c", true))
+    );
+    assert_eq!(
+        pattern::parse_substitution("S/[0-9]+"),
+        Some(("S", "[0-9]+".to_owned(), "", false))
+    );
+    assert_eq!(pattern::parse_substitution("S"), None);
+}
+
+#[test]
+fn lex_conditional() {
+    let mut lexer = Lexer::new("[[ $S =~ ^(a|\"b c\")$ ]]; echo".chars().collect());
+
+    assert_eq!(
+        lexer.next(),
+        Some(Token::Conditional(vec![
+            "$S".to_owned(),
+            "=~".to_owned(),
+            "^(a|\"b c\")$".to_owned(),
+        ]))
+    );
+    assert_eq!(lexer.next(), Some(Token::Semicolon));
+    assert_eq!(lexer.next(), Some(Token::String("echo".to_owned())));
+}
+
+#[test]
+fn conditional_sets_and_unsets_regex() {
+    let mut state = ShellState::default();
+
+    shell::eval(
+        &mut state,
+        "=S release-1.24; [[ $S =~ ^([a-z]+)-([0-9]+) ]]".to_owned(),
+    )
+    .unwrap();
+
+    assert_eq!(state.status, 0);
+    assert_eq!(
+        state.value("NSH_REGEX"),
+        Some(Value::Array(vec![
+            "release-1".to_owned(),
+            "release".to_owned(),
+            "1".to_owned(),
+        ]))
+    );
+
+    shell::eval(&mut state, "[[ $S =~ ^x ]]".to_owned()).unwrap();
+
+    assert_eq!(state.status, 1);
+    assert_eq!(state.value("NSH_REGEX"), None);
+}